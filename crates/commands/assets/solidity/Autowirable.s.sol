@@ -41,8 +41,9 @@ abstract contract Autowirable is Script {
     }
 
     modifier autowire(Sources.Source source) {
+        // abi.encode(source) works for both the Source enum and the uint256 Source of sharded libraries
         address injectedAddress = wiringMechanism.wire(
-            abi.encode(uint256(source)),
+            abi.encode(source),
             IWiringMechanism.SupportedWiring.PLAIN
         );
         console.log(
@@ -104,7 +105,7 @@ abstract contract Autowirable is Script {
 
     modifier nickwire(Sources.Source source, ShortString nickname) {
         address injectedAddress = wiringMechanism.wire(
-            abi.encode(uint256(source), nickname),
+            abi.encode(source, nickname),
             IWiringMechanism.SupportedWiring.PLAIN_NICKNAMED
         );
         console.log(
@@ -132,7 +133,7 @@ abstract contract Autowirable is Script {
         console.log("Autowired lookup for source:", source.toString());
         address[] memory sortedInjectedAddresses = wiringMechanism
             .getWiredVariants(
-                abi.encodePacked(uint256(source)),
+                abi.encode(source),
                 IWiringMechanism.SupportedWiring.PLAIN
             );
         if (
@@ -156,10 +157,7 @@ abstract contract Autowirable is Script {
         );
         address[] memory sortedInjectedAddresses = wiringMechanism
             .getWiredVariants(
-                abi.encodePacked(
-                    uint256(source),
-                    ShortStrings.toShortString(nickname)
-                ),
+                abi.encode(source, ShortStrings.toShortString(nickname)),
                 IWiringMechanism.SupportedWiring.PLAIN_NICKNAMED
             );
         if (
@@ -44,52 +44,52 @@ contract Eip4337Configuration is IConfiguration {
     function startAutowiringSources() external override {
         address factory = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString("MSAFactory")
                 )
             )
             .toAddress();
         address eoaKeyValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.EOAKeyValidator.toString()
+                        Sources.EOAKeyValidator.toString()
                     )
                 )
             )
             .toAddress();
         address sessionKeyValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.SessionKeyValidator.toString()
+                        Sources.SessionKeyValidator.toString()
                     )
                 )
             )
             .toAddress();
         address webAuthnValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.WebAuthnValidator.toString()
+                        Sources.WebAuthnValidator.toString()
                     )
                 )
             )
             .toAddress();
         address recoveryExecutor = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.GuardianBasedRecoveryExecutor.toString()
+                        Sources.GuardianBasedRecoveryExecutor.toString()
                     )
                 )
             )
             .toAddress();
         address guardianExecutor = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.GuardianExecutor.toString()
+                        Sources.GuardianExecutor.toString()
                     )
                 )
             )
@@ -117,6 +117,6 @@ contract Eip4337Configuration is IConfiguration {
 
     function getAccountSourceKey() public view returns (string memory) {
         return
-            Sources.Source.ModularSmartAccount.getFullNicknamedName(nickname);
+            Sources.ModularSmartAccount.getFullNicknamedName(nickname);
     }
 }
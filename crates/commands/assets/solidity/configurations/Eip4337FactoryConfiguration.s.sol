@@ -45,20 +45,20 @@ contract Eip4337FactoryConfiguration is IConfiguration {
     function startAutowiringSources() external override {
         vm.startBroadcast();
         address accountImpl = address(new ModularSmartAccount());
-        config.set(Sources.Source.ModularSmartAccount.toString(), accountImpl);
+        config.set(Sources.ModularSmartAccount.toString(), accountImpl);
         address beacon = address(
             new UpgradeableBeacon(accountImpl, beaconOwner)
         );
-        config.set(Sources.Source.UpgradeableBeacon.toString(), beacon);
+        config.set(Sources.UpgradeableBeacon.toString(), beacon);
         address factoryImpl = address(new MSAFactory(beacon));
-        config.set(Sources.Source.MSAFactory.toString(), factoryImpl);
+        config.set(Sources.MSAFactory.toString(), factoryImpl);
         vm.stopBroadcast();
         TUPConfiguration tupConfig = new TUPConfiguration(
             vm,
             config,
             beaconOwner,
             factoryImpl,
-            Sources.Source.MSAFactory
+            Sources.MSAFactory
         );
         tupConfig.startAutowiringSources();
     }
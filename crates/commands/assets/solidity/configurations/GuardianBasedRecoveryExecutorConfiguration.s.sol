@@ -47,18 +47,18 @@ contract GuardianBasedRecoveryExecutorConfiguration is IConfiguration {
     function startAutowiringSources() external override {
         address webAuthnValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.WebAuthnValidator.toString()
+                        Sources.WebAuthnValidator.toString()
                     )
                 )
             )
             .toAddress();
         address eoaKeyValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.EOAKeyValidator.toString()
+                        Sources.EOAKeyValidator.toString()
                     )
                 )
             )
@@ -77,7 +77,7 @@ contract GuardianBasedRecoveryExecutorConfiguration is IConfiguration {
             config,
             admin,
             recoveryExecutorImpl,
-            Sources.Source.GuardianBasedRecoveryExecutor
+            Sources.GuardianBasedRecoveryExecutor
         );
         bytes memory initData = abi.encodeCall(
             GuardianBasedRecoveryExecutor.initialize,
@@ -41,18 +41,18 @@ contract GuardianExecutorConfiguration is IConfiguration {
     function startAutowiringSources() external override {
         address webAuthnValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.WebAuthnValidator.toString()
+                        Sources.WebAuthnValidator.toString()
                     )
                 )
             )
             .toAddress();
         address eoaKeyValidator = config
             .get(
-                Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+                Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                     ShortStrings.toShortString(
-                        Sources.Source.EOAKeyValidator.toString()
+                        Sources.EOAKeyValidator.toString()
                     )
                 )
             )
@@ -63,7 +63,7 @@ contract GuardianExecutorConfiguration is IConfiguration {
             new GuardianExecutor(webAuthnValidator, eoaKeyValidator)
         );
         config.set(
-            Sources.Source.GuardianExecutor.toString(),
+            Sources.GuardianExecutor.toString(),
             guardianExecutorImpl
         );
 
@@ -72,7 +72,7 @@ contract GuardianExecutorConfiguration is IConfiguration {
             config,
             proxyOwner,
             guardianExecutorImpl,
-            Sources.Source.GuardianExecutor
+            Sources.GuardianExecutor
         );
         tupConfig.startAutowiringSources();
     }
@@ -59,7 +59,7 @@ contract TUPConfiguration is IConfiguration {
 
     function getProxySourceKey() public view returns (string memory) {
         return
-            Sources.Source.TransparentUpgradeableProxy.getFullNicknamedName(
+            Sources.TransparentUpgradeableProxy.getFullNicknamedName(
                 ShortStrings.toShortString(implementationSource.toString())
             );
     }
@@ -0,0 +1,285 @@
+use crate::{
+    EnumPolicy,
+    utils::{remark, success, warning},
+};
+use clap::Parser;
+use reflections_core::{
+    Result,
+    artifacts::ArtifactIndex,
+    config::{Paths, ReflectionsConfig},
+    create2::{Address, create2_address, decode_hex, encode_hex, keccak256},
+    generator::{GeneratorOptions, source_variants},
+    imports::ImportResolver,
+    model::Dependency,
+    parser::DiscoveryFilter,
+};
+use serde::Serialize;
+use std::fs;
+
+/// Predict the CREATE2 addresses of the sources
+#[derive(Debug, Clone, Parser, bon::Builder)]
+#[allow(clippy::duplicated_attributes)]
+#[builder(on(String, into))]
+#[clap(after_help = "For more information, read the README.md")]
+#[non_exhaustive]
+pub struct Addresses {
+    /// The address deploying the sources with `Create2.deploy`
+    #[arg(short, long, value_name = "ADDRESS")]
+    pub deployer: String,
+
+    /// Also predict the address of every source deployed with this nickname
+    ///
+    /// Can be passed multiple times. Nicknames are salted like `toSalt(Source, ShortString)`
+    /// does and hold at most 31 bytes.
+    #[arg(short, long)]
+    #[builder(default)]
+    pub nickname: Vec<String>,
+
+    /// Print JSON instead of a table
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub json: bool,
+
+    /// Write the addresses to this file instead of printing them
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Path to the contracts directory (relative to project root)
+    ///
+    /// Defaults to `src` from foundry.toml.
+    #[arg(short, long)]
+    pub contracts_dir: Option<String>,
+
+    /// Path to the forge build output directory
+    ///
+    /// Defaults to `out` from foundry.toml.
+    #[arg(short, long)]
+    pub artifacts_dir: Option<String>,
+
+    /// Which contracts get a `Source` variant, like `generate --enum-policy`
+    #[arg(long, value_enum, default_value = "all")]
+    #[builder(default)]
+    pub enum_policy: EnumPolicy,
+
+    /// Only include contracts inheriting (directly or transitively) from this contract
+    ///
+    /// Can be passed multiple times, like `generate --inherits-from`.
+    #[arg(long, value_name = "CONTRACT")]
+    #[builder(default)]
+    pub inherits_from: Vec<String>,
+
+    /// Name duplicate contracts like `generate --disambiguate` does
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub disambiguate: bool,
+
+    /// Only read contract files matching this glob (relative to the contracts directory)
+    ///
+    /// Can be passed multiple times. Overrides `include` in the `[generate]` section of
+    /// reflections.toml.
+    #[arg(long, value_name = "GLOB")]
+    #[builder(default)]
+    pub include: Vec<String>,
+
+    /// Skip contract files matching this glob (relative to the contracts directory)
+    ///
+    /// Can be passed multiple times. Overrides `exclude` in the `[generate]` section of
+    /// reflections.toml.
+    #[arg(long, value_name = "GLOB")]
+    #[builder(default)]
+    pub exclude: Vec<String>,
+
+    /// Skip Foundry test files (`*.t.sol`)
+    ///
+    /// `--skip-tests false` reads them when the config skips them.
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub skip_tests: Option<bool>,
+
+    /// Skip Foundry script files (`*.s.sol`)
+    ///
+    /// `--skip-scripts false` reads them when the config skips them.
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub skip_scripts: Option<bool>,
+
+    /// Also include the contracts of a dependency file, resolved through the remappings
+    ///
+    /// Written `<path>` or `<path>:<contract>`, like `generate --dependency`. Can be passed
+    /// multiple times. Overrides `dependencies` in the `[generate]` section of reflections.toml.
+    #[arg(long, value_name = "PATH[:CONTRACT]")]
+    #[builder(default)]
+    pub dependency: Vec<String>,
+
+    /// Project id of the salt namespace mixed into `toSalt`
+    ///
+    /// Projects deploying equally named contracts through the same CREATE2 factory get different
+    /// addresses with different ids. Overrides `project` in the `[generate.salt-namespace]`
+    /// section of reflections.toml.
+    #[arg(long, value_name = "ID")]
+    pub salt_project: Option<String>,
+
+    /// Version of the salt namespace mixed into `toSalt`
+    ///
+    /// Bump it to redeploy contracts under the same names. Overrides `version` in the
+    /// `[generate.salt-namespace]` section of reflections.toml.
+    #[arg(long, value_name = "VERSION")]
+    pub salt_version: Option<String>,
+
+    /// Mix `block.chainid` into `toSalt`, giving the sources different addresses on every chain
+    ///
+    /// Requires `--chain-id`. Overrides `chain-id` in the `[generate.salt-namespace]` section of
+    /// reflections.toml, which `--salt-chain-id false` turns off.
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub salt_chain_id: Option<bool>,
+
+    /// The chain to predict the addresses on, if the salt namespace includes the chain id
+    #[arg(long, value_name = "ID")]
+    pub chain_id: Option<u64>,
+}
+
+/// The predicted address of a source, deployed with or without nickname.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictedAddress {
+    /// The name `toString` returns for the source.
+    pub source: String,
+    pub nickname: Option<String>,
+    pub salt: String,
+    pub init_code_hash: String,
+    pub address: String,
+}
+
+pub(crate) async fn addresses_command(paths: &Paths, cmd: Addresses) -> Result<()> {
+    let deployer: Address = cmd.deployer.parse()?;
+
+    // the same contracts and salts `generate` uses
+    let config = ReflectionsConfig::load(paths.root.join("reflections.toml"))?.generate;
+    let mut salt_namespace = config.salt_namespace;
+    if let Some(project) = cmd.salt_project {
+        salt_namespace.project = project;
+    }
+    if let Some(version) = cmd.salt_version {
+        salt_namespace.version = version;
+    }
+    if let Some(chain_id) = cmd.salt_chain_id {
+        salt_namespace.chain_id = chain_id;
+    }
+    let nicknames: Vec<Option<&str>> =
+        std::iter::once(None).chain(cmd.nickname.iter().map(|n| Some(n.as_str()))).collect();
+    for nickname in &nicknames {
+        // fail before reading the artifacts
+        salt_namespace.salt(cmd.chain_id, "", *nickname)?;
+    }
+
+    let contracts_dir = cmd.contracts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.src));
+    let artifacts_dir = cmd.artifacts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.out));
+    let filter = DiscoveryFilter {
+        include: if cmd.include.is_empty() { config.include } else { cmd.include },
+        exclude: if cmd.exclude.is_empty() { config.exclude } else { cmd.exclude },
+        skip_tests: cmd.skip_tests.unwrap_or(config.skip_tests),
+        skip_scripts: cmd.skip_scripts.unwrap_or(config.skip_scripts),
+    };
+    let dependencies = if cmd.dependency.is_empty() { config.dependencies } else { cmd.dependency };
+    let dependencies = dependencies
+        .iter()
+        .map(|d| d.parse())
+        .collect::<std::result::Result<Vec<Dependency>, _>>()?;
+
+    remark!("Reading creation code of {} from artifacts in {}", contracts_dir, artifacts_dir);
+    let index = ArtifactIndex::load(&paths.root, &artifacts_dir)?;
+    let mut contracts =
+        index.contracts(&contracts_dir, &filter, &dependencies, &ImportResolver::new(paths)?)?;
+    if !cmd.inherits_from.is_empty() {
+        let all = contracts.clone();
+        // dependencies were requested explicitly and are kept regardless
+        let src_path = paths.root.join(&contracts_dir);
+        contracts.retain(|c| {
+            !c.path.starts_with(&src_path) ||
+                cmd.inherits_from.iter().any(|base| c.inherits_from(base, &all))
+        });
+    }
+    let options = GeneratorOptions {
+        enum_policy: cmd.enum_policy.into(),
+        disambiguate: cmd.disambiguate,
+        ..Default::default()
+    };
+
+    let mut predicted = Vec::new();
+    for (ident, contract) in source_variants(&contracts, &options)? {
+        if !contract.kind.is_deployable() {
+            continue;
+        }
+        if !contract.constructor_params.is_empty() {
+            warning!(
+                "{} takes constructor arguments, which are part of its init code, skipping",
+                ident
+            );
+            continue;
+        }
+        let Some(code) = index.creation_code(contract)? else {
+            warning!("{} has no creation code in the artifacts, skipping", ident);
+            continue;
+        };
+        if code.contains("__$") {
+            warning!("{} links against libraries, skipping", ident);
+            continue;
+        }
+        let Some(code) = decode_hex(code) else {
+            warning!("{} has invalid creation code, skipping", ident);
+            continue;
+        };
+        let init_code_hash = keccak256(&code);
+        for nickname in &nicknames {
+            let salt = salt_namespace.salt(cmd.chain_id, &ident, *nickname)?;
+            predicted.push(PredictedAddress {
+                source: ident.clone(),
+                nickname: nickname.map(str::to_string),
+                salt: format!("0x{}", encode_hex(&salt)),
+                init_code_hash: format!("0x{}", encode_hex(&init_code_hash)),
+                address: create2_address(&deployer, &salt, &init_code_hash).to_string(),
+            });
+        }
+    }
+
+    success!("Predicted {} addresses for deployer {}", predicted.len(), deployer);
+
+    let rendered = if cmd.json {
+        let mut json = serde_json::to_string_pretty(&predicted).map_err(|e| {
+            reflections_core::ReflectionsError::IOError(std::io::Error::other(format!(
+                "Failed to serialize addresses to JSON: {e}"
+            )))
+        })?;
+        json.push('\n');
+        json
+    } else {
+        render_table(&predicted)
+    };
+
+    match cmd.output {
+        Some(output) => {
+            fs::write(paths.root.join(&output), rendered)?;
+            success!("Addresses saved to: {}", output);
+        }
+        None => print!("{rendered}"),
+    }
+
+    Ok(())
+}
+
+fn render_table(predicted: &[PredictedAddress]) -> String {
+    let rows: Vec<[&str; 3]> = predicted
+        .iter()
+        .map(|p| [p.source.as_str(), p.nickname.as_deref().unwrap_or("-"), p.address.as_str()])
+        .collect();
+    let source_width = rows.iter().map(|r| r[0].len()).chain([6]).max().unwrap_or_default();
+    let nickname_width = rows.iter().map(|r| r[1].len()).chain([8]).max().unwrap_or_default();
+
+    let mut table = String::new();
+    for [source, nickname, address] in
+        std::iter::once(["SOURCE", "NICKNAME", "ADDRESS"]).chain(rows)
+    {
+        table.push_str(&format!(
+            "{source:<source_width$}  {nickname:<nickname_width$}  {address}\n"
+        ));
+    }
+    table
+}
@@ -1,11 +1,17 @@
-use crate::utils::{remark, success};
+use crate::utils::{remark, success, warning};
 use clap::Parser;
 use reflections_core::{
     Result,
     config::Paths,
+    parser::{ContractInfo, discover_contracts},
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    cell::OnceCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 /// Collect ABIs and group by NatSpec tags
 #[derive(Debug, Clone, Parser, bon::Builder)]
@@ -15,9 +21,10 @@ use std::{collections::HashMap, fs, path::PathBuf};
 #[non_exhaustive]
 pub struct Collect {
     /// Path to the forge build output directory
-    #[arg(short, long, default_value = "out")]
-    #[builder(default)]
-    pub artifacts_dir: String,
+    ///
+    /// Defaults to `out` from foundry.toml.
+    #[arg(short, long)]
+    pub artifacts_dir: Option<String>,
 
     /// Output file for collected ABIs
     #[arg(short, long, default_value = "abis.json")]
@@ -28,6 +35,27 @@ pub struct Collect {
     #[arg(short, long, default_value = "@custom:swagger")]
     #[builder(default)]
     pub tag: String,
+
+    /// How to group the collected ABIs
+    #[arg(short, long, value_enum, default_value = "tag")]
+    #[builder(default)]
+    pub group_by: GroupBy,
+
+    /// Path to the contracts directory, used to resolve inheritance when grouping by base
+    ///
+    /// Defaults to `src` from foundry.toml.
+    #[arg(short, long)]
+    pub contracts_dir: Option<String>,
+}
+
+/// The grouping strategy for collected ABIs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// Group by the value of the NatSpec tag given with `--tag`
+    #[default]
+    Tag,
+    /// Group by the top-most base contract discovered in the contracts directory
+    Base,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,17 +83,40 @@ pub struct ABICollection {
 }
 
 pub(crate) async fn collect_command(paths: &Paths, cmd: Collect) -> Result<()> {
-    remark!("Collecting ABIs from {}", cmd.artifacts_dir);
+    let artifacts_dir = cmd.artifacts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.out));
+    remark!("Collecting ABIs from {}", artifacts_dir);
 
-    let artifacts_path = paths.root.join(&cmd.artifacts_dir);
+    let artifacts_path = paths.root.join(&artifacts_dir);
     
     if !artifacts_path.exists() {
         return Err(reflections_core::ReflectionsError::IOError(std::io::Error::new(
             std::io::ErrorKind::NotFound,
-            format!("Artifacts directory not found: {}. Run 'forge build' first.", cmd.artifacts_dir)
+            format!("Artifacts directory not found: {artifacts_dir}. Run 'forge build' first.")
         )));
     }
 
+    let contracts_dir = cmd.contracts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.src));
+    let sources = match cmd.group_by {
+        GroupBy::Tag => OnceCell::new(),
+        GroupBy::Base => {
+            remark!("Resolving inheritance from {}", contracts_dir);
+            OnceCell::from(discover_contracts(&paths.root, &contracts_dir)?)
+        }
+    };
+    // Sources are only a fallback for artifacts built without metadata, so they are parsed on
+    // first use, and sources that do not parse leave those artifacts ungrouped
+    let contracts = || {
+        sources.get_or_init(|| {
+            if !paths.root.join(&contracts_dir).is_dir() {
+                return Vec::new();
+            }
+            discover_contracts(&paths.root, &contracts_dir).unwrap_or_else(|e| {
+                warning!("Could not read the NatSpec in {}: {}", contracts_dir, e);
+                Vec::new()
+            })
+        })
+    };
+
     let mut collection = ABICollection {
         grouped: HashMap::new(),
         ungrouped: Vec::new(),
@@ -103,8 +154,13 @@ pub(crate) async fn collect_command(paths: &Paths, cmd: Collect) -> Result<()> {
                     .to_string_lossy()
                     .to_string();
 
-                // Try to extract group from metadata
-                let group = extract_group_from_metadata(&content, &cmd.tag);
+                let group = match cmd.group_by {
+                    // Try to extract group from metadata, then from the NatSpec in the source
+                    GroupBy::Tag => extract_group_from_metadata(&content, &cmd.tag).or_else(|| {
+                        extract_group_from_natspec(&json_file, &contract_name, contracts(), &cmd.tag)
+                    }),
+                    GroupBy::Base => extract_group_from_bases(&json_file, &contract_name, contracts()),
+                };
 
                 let collected_abi = CollectedABI {
                     contract_name: contract_name.clone(),
@@ -197,6 +253,39 @@ fn extract_group_from_metadata(content: &str, tag: &str) -> Option<String> {
     None
 }
 
+fn extract_group_from_natspec(
+    json_file: &Path,
+    contract_name: &str,
+    contracts: &[ContractInfo],
+    tag: &str,
+) -> Option<String> {
+    let contract = find_contract(json_file, contract_name, contracts)?;
+    contract.natspec.tag(tag).map(String::from)
+}
+
+fn extract_group_from_bases(
+    json_file: &Path,
+    contract_name: &str,
+    contracts: &[ContractInfo],
+) -> Option<String> {
+    let contract = find_contract(json_file, contract_name, contracts)?;
+    contract.root_base(contracts).map(|base| base.name.clone())
+}
+
+/// Find the discovered contract an artifact was built from.
+fn find_contract<'a>(
+    json_file: &Path,
+    contract_name: &str,
+    contracts: &'a [ContractInfo],
+) -> Option<&'a ContractInfo> {
+    // Forge writes artifacts to `out/<SourceFile>.sol/<Contract>.json`
+    let source_file = json_file.parent().and_then(|p| p.file_name());
+    contracts
+        .iter()
+        .find(|c| c.name == contract_name && c.path.file_name() == source_file)
+        .or_else(|| contracts.iter().find(|c| c.name == contract_name))
+}
+
 fn extract_tag_from_doc(doc: &serde_json::Value, tag: &str) -> Option<String> {
     // Handle different tag formats
     match tag {
@@ -1,12 +1,25 @@
-use crate::utils::{remark, success};
+use crate::{
+    EnumPolicy, ShardBy,
+    utils::{remark, success, warning},
+};
 use clap::Parser;
 use reflections_core::{
     Result,
-    config::Paths,
-    generator::{GeneratorOptions, generate_sources_library},
-    parser::discover_contracts,
+    artifacts::ArtifactIndex,
+    cache::DiscoveryCache,
+    config::{Paths, ReflectionsConfig},
+    errors::{GeneratorError, ImportError},
+    generator::{
+        AUTO_PRAGMA, GeneratorOptions, diff_library, generate_libraries, generate_smoke_tests,
+        resolve_pragma, source_variants, stale_init_code_hashes, variant_names, variant_shards,
+    },
+    imports::{ImportGraph, ImportResolver},
+    lockfile::{LOCKFILE_PATH, SourceLock},
+    model::{Dependency, ProjectModel},
+    parser::{DiscoveryFilter, parse_source_unit},
+    template::{LibraryTemplate, TEMPLATE_DIR, TemplatePaths},
 };
-use std::fs;
+use std::{collections::BTreeMap, fs};
 
 /// Generate Solidity reflection library from contracts
 #[derive(Debug, Clone, Parser, bon::Builder)]
@@ -16,14 +29,16 @@ use std::fs;
 #[non_exhaustive]
 pub struct Generate {
     /// Path to the contracts directory (relative to project root)
-    #[arg(short, long, default_value = "src")]
-    #[builder(default)]
-    pub contracts_dir: String,
+    ///
+    /// Defaults to `src` from foundry.toml.
+    #[arg(short, long)]
+    pub contracts_dir: Option<String>,
 
     /// Output file for generated reflection library
-    #[arg(short, long, default_value = "scripts/reflections/di/libraries/Sources.s.sol")]
-    #[builder(default)]
-    pub output: String,
+    ///
+    /// Defaults to `reflections/di/libraries/Sources.s.sol` inside `script` from foundry.toml.
+    #[arg(short, long)]
+    pub output: Option<String>,
 
     /// Name of the generated library
     #[arg(long, default_value = "Sources")]
@@ -36,57 +51,499 @@ pub struct Generate {
     pub license: String,
 
     /// Solidity version pragma
+    ///
+    /// Checked against the pragmas of the imported files and of everything they import. Use
+    /// `auto` for the tightest pragma compatible with all of them.
     #[arg(long, default_value = "^0.8.24")]
     #[builder(default)]
     pub solidity_version: String,
+
+    /// Which contracts get a variant in the `Source` enum
+    ///
+    /// Interfaces and abstract contracts have no creation code, so `toCreationCode` reverts for
+    /// them when they are included.
+    #[arg(long, value_enum, default_value = "all")]
+    #[builder(default)]
+    pub enum_policy: EnumPolicy,
+
+    /// Only include contracts inheriting (directly or transitively) from this contract
+    ///
+    /// Can be passed multiple times; a contract is included if it inherits from any of them.
+    #[arg(long, value_name = "CONTRACT")]
+    #[builder(default)]
+    pub inherits_from: Vec<String>,
+
+    /// Alias contracts declared in more than one file instead of failing
+    ///
+    /// Duplicates are imported as `Name_<dir>` (e.g. `import { Vault as Vault_v2 }`) and get
+    /// matching enum variants.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub disambiguate: bool,
+
+    /// Only read contract files matching this glob (relative to the contracts directory)
+    ///
+    /// Can be passed multiple times. Overrides `include` in the `[generate]` section of
+    /// reflections.toml.
+    #[arg(long, value_name = "GLOB")]
+    #[builder(default)]
+    pub include: Vec<String>,
+
+    /// Skip contract files matching this glob (relative to the contracts directory)
+    ///
+    /// Can be passed multiple times, e.g. `--exclude '**/mocks/**'`. Overrides `exclude` in the
+    /// `[generate]` section of reflections.toml.
+    #[arg(long, value_name = "GLOB")]
+    #[builder(default)]
+    pub exclude: Vec<String>,
+
+    /// Skip Foundry test files (`*.t.sol`)
+    ///
+    /// `--skip-tests false` reads them when the config skips them.
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub skip_tests: Option<bool>,
+
+    /// Skip Foundry script files (`*.s.sol`)
+    ///
+    /// `--skip-scripts false` reads them when the config skips them.
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub skip_scripts: Option<bool>,
+
+    /// Fail instead of warning when the pragma conflicts with the imported files
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub strict_pragma: bool,
+
+    /// Also include the contracts of a dependency file, resolved through the remappings
+    ///
+    /// Written `<path>` or `<path>:<contract>`, e.g.
+    /// `@openzeppelin/contracts/proxy/ERC1967/ERC1967Proxy.sol:ERC1967Proxy`. Can be passed
+    /// multiple times. Overrides `dependencies` in the `[generate]` section of reflections.toml.
+    #[arg(long, value_name = "PATH[:CONTRACT]")]
+    #[builder(default)]
+    pub dependency: Vec<String>,
+
+    /// Parse every file instead of reusing `.reflections/cache/discovery.json`
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub no_cache: bool,
+
+    /// Read the contracts from the forge build artifacts instead of parsing the sources
+    ///
+    /// Run `forge build` first. Artifacts give the exact contract names, source paths and kinds.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub from_artifacts: bool,
+
+    /// Path to the forge build output directory, used with `--from-artifacts` and
+    /// `--init-code-hashes`
+    ///
+    /// Defaults to `out` from foundry.toml.
+    #[arg(long)]
+    pub artifacts_dir: Option<String>,
+
+    /// Do not generate `fromString(string)`
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub no_from_string: bool,
+
+    /// Do not generate `all()` and `count()`
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub no_enumeration: bool,
+
+    /// Do not generate `isDeployable(Source)`
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub no_is_deployable: bool,
+
+    /// Generate `initCodeHash(Source)` and `predictAddress(Source, address[, ShortString])`
+    ///
+    /// The hashes are taken from the creation code in the forge build artifacts, so run
+    /// `forge build` first. Contracts can then compute the addresses of their peers without
+    /// embedding their creation code. With `--check`, hashes changed by a rebuild are reported.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub init_code_hashes: bool,
+
+    /// Also generate a Foundry test deploying every deployable source
+    ///
+    /// Written to `reflections/<library_name>.t.sol` inside `test` from foundry.toml. Every
+    /// source is deployed through `StdConfigBasedWiring` with the debug configuration, checking
+    /// that it gets code at its predicted address, so run `reflections init` first. Sources with
+    /// constructor parameters are deployed through `assertDeployed` with zero-valued arguments;
+    /// only those whose parameter types need their own imports are skipped.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub smoke_tests: bool,
+
+    /// Project id of the salt namespace mixed into `toSalt`
+    ///
+    /// Projects deploying equally named contracts through the same CREATE2 factory get different
+    /// addresses with different ids. Overrides `project` in the `[generate.salt-namespace]`
+    /// section of reflections.toml.
+    #[arg(long, value_name = "ID")]
+    pub salt_project: Option<String>,
+
+    /// Version of the salt namespace mixed into `toSalt`
+    ///
+    /// Bump it to redeploy contracts under the same names. Overrides `version` in the
+    /// `[generate.salt-namespace]` section of reflections.toml.
+    #[arg(long, value_name = "VERSION")]
+    pub salt_version: Option<String>,
+
+    /// Mix `block.chainid` into `toSalt`, giving the sources different addresses on every chain
+    ///
+    /// `toSalt` and `predictAddress` then read the chain and are `view` instead of `pure`.
+    /// `--salt-chain-id false` turns it off when the config turns it on.
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub salt_chain_id: Option<bool>,
+
+    /// Split the `Source` enum over several libraries behind a dispatcher
+    ///
+    /// Enums hold at most 256 members, and one library with the creation code of every contract
+    /// can exceed compiler limits. `directory` makes one library per top-level directory of the
+    /// contracts directory, `count` libraries of at most `--shard-size` contracts. The library at
+    /// `--output` then dispatches to them, with `Sources.<Contract>` constants as sources.
+    #[arg(long, value_enum, default_value = "none")]
+    #[builder(default)]
+    pub shard_by: ShardBy,
+
+    /// The most contracts per library when sharding by count
+    #[arg(long, default_value_t = 200)]
+    #[builder(default = 200)]
+    pub shard_size: usize,
+
+    /// Directory with templates overriding the built-in ones (relative to project root)
+    ///
+    /// A `library.sol.j2` in this directory replaces the template of the generated library, a
+    /// `dispatcher.sol.j2` the template of the dispatcher of sharded libraries.
+    /// Defaults to `template-dir` in the `[generate]` section of reflections.toml, or
+    /// `.reflections/templates`.
+    #[arg(long, value_name = "DIR")]
+    pub template_dir: Option<String>,
+
+    /// Fail if the output file is not up to date, printing a diff, instead of writing it
+    ///
+    /// Nothing is written in this mode, neither the library, reflections.lock nor the discovery
+    /// cache.
+    #[arg(long, default_value_t = false)]
+    #[builder(default)]
+    pub check: bool,
 }
 
 pub(crate) async fn generate_command(paths: &Paths, cmd: Generate) -> Result<()> {
-    remark!("Discovering contracts in {}", cmd.contracts_dir);
+    let contracts_dir = cmd.contracts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.src));
+    let output = cmd.output.unwrap_or_else(|| {
+        paths.relative_to_root(paths.script.join("reflections/di/libraries/Sources.s.sol"))
+    });
+
+    let config = ReflectionsConfig::load(paths.root.join("reflections.toml"))?.generate;
+    let filter = DiscoveryFilter {
+        include: if cmd.include.is_empty() { config.include } else { cmd.include },
+        exclude: if cmd.exclude.is_empty() { config.exclude } else { cmd.exclude },
+        skip_tests: cmd.skip_tests.unwrap_or(config.skip_tests),
+        skip_scripts: cmd.skip_scripts.unwrap_or(config.skip_scripts),
+    };
+
+    let mut salt_namespace = config.salt_namespace;
+    if let Some(project) = cmd.salt_project {
+        salt_namespace.project = project;
+    }
+    if let Some(version) = cmd.salt_version {
+        salt_namespace.version = version;
+    }
+    if let Some(chain_id) = cmd.salt_chain_id {
+        salt_namespace.chain_id = chain_id;
+    }
+
+    let template_dir =
+        cmd.template_dir.or(config.template_dir).unwrap_or_else(|| TEMPLATE_DIR.to_string());
 
-    let contracts = discover_contracts(&paths.root, &cmd.contracts_dir)?;
+    let dependencies = if cmd.dependency.is_empty() { config.dependencies } else { cmd.dependency };
+    let dependencies = dependencies
+        .iter()
+        .map(|d| d.parse())
+        .collect::<std::result::Result<Vec<Dependency>, _>>()?;
+
+    let artifacts_dir = cmd.artifacts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.out));
+    let mut contracts = if cmd.from_artifacts || config.from_artifacts {
+        remark!("Reading contracts of {} from artifacts in {}", contracts_dir, artifacts_dir);
+        ArtifactIndex::load(&paths.root, &artifacts_dir)?.contracts(
+            &contracts_dir,
+            &filter,
+            &dependencies,
+            &ImportResolver::new(paths)?,
+        )?
+    } else {
+        remark!("Discovering contracts in {}", contracts_dir);
+        let mut model = if cmd.no_cache {
+            ProjectModel::discover(&paths.root, &contracts_dir, &filter)?
+        } else {
+            let mut cache = DiscoveryCache::load(&paths.root);
+            let model =
+                ProjectModel::discover_cached(&paths.root, &contracts_dir, &filter, &mut cache)?;
+            if !cmd.check {
+                cache.save()?;
+            }
+            model
+        };
+        if !dependencies.is_empty() {
+            remark!("Adding {} dependencies", dependencies.len());
+            model.add_dependencies(&dependencies, &ImportResolver::new(paths)?)?;
+        }
+        model.contracts()
+    };
+
+    if !cmd.inherits_from.is_empty() {
+        let all = contracts.clone();
+        // dependencies were requested explicitly and are kept regardless
+        let src_path = paths.root.join(&contracts_dir);
+        contracts.retain(|c| {
+            !c.path.starts_with(&src_path) ||
+                cmd.inherits_from.iter().any(|base| c.inherits_from(base, &all))
+        });
+        remark!("Keeping contracts inheriting from {}", cmd.inherits_from.join(", "));
+    }
 
     if contracts.is_empty() {
-        remark!("No contracts found in {}", cmd.contracts_dir);
+        remark!("No contracts found in {}", contracts_dir);
         return Ok(());
     }
 
     success!("Found {} contracts", contracts.len());
 
     for contract in &contracts {
-        remark!(
-            "  - {} ({})",
-            contract.name,
-            if contract.is_library {
-                "library"
-            } else if contract.is_interface {
-                "interface"
-            } else {
-                "contract"
-            }
-        );
+        remark!("  - {} ({})", contract.name, contract.kind);
     }
 
     remark!("Generating reflection library...");
 
-    let options = GeneratorOptions {
+    let mut options = GeneratorOptions {
         library_name: cmd.library_name.clone(),
         license: cmd.license,
         solidity_version: cmd.solidity_version,
+        enum_policy: cmd.enum_policy.into(),
+        disambiguate: cmd.disambiguate,
+        lock: None,
+        from_string: !cmd.no_from_string,
+        enumeration: !cmd.no_enumeration,
+        is_deployable: !cmd.no_is_deployable,
+        init_code_hashes: None,
+        salt_namespace,
+        sharding: cmd.shard_by.into(),
+        shard_size: cmd.shard_size,
+        template: LibraryTemplate::load(paths.root.join(&template_dir))?,
+        dispatcher_template: LibraryTemplate::load_dispatcher(paths.root.join(&template_dir))?,
+        paths: TemplatePaths { contracts_dir, output: output.clone() },
+    };
+    for template in [&options.template, &options.dispatcher_template] {
+        let Some(path) = &template.path else { continue };
+        remark!("Using template {}", path);
+        if !options.salt_namespace.is_empty() && !template.uses_salt_namespace() {
+            warning!(
+                "{} does not reference SALT_NAMESPACE, so its salts ignore the salt namespace",
+                path
+            );
+        }
+    }
+
+    // the imported files are compiled along with the library, and so is everything they import
+    let imported = contracts
+        .iter()
+        .filter(|c| options.enum_policy.includes(c.kind))
+        .map(|c| paths.relative_to_root(&c.path));
+    let imports = ImportGraph::from_files(imported, &ImportResolver::new(paths)?);
+    match resolve_pragma(&contracts, &imports, &options) {
+        Ok(pragma) => {
+            if options.solidity_version == AUTO_PRAGMA {
+                remark!("Using pragma solidity {}", pragma);
+            }
+            options.solidity_version = pragma;
+        }
+        Err(e) if !cmd.strict_pragma && options.solidity_version != AUTO_PRAGMA => {
+            warning!("{}", e);
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    if cmd.init_code_hashes || config.init_code_hashes {
+        remark!("Reading init code hashes from artifacts in {}", artifacts_dir);
+        let index = ArtifactIndex::load(&paths.root, &artifacts_dir)?;
+        // A source importing the library compiles the hashes in, so its own would never settle
+        let graph =
+            ImportGraph::from_dir(&options.paths.contracts_dir, &ImportResolver::new(paths)?);
+        let shard_prefix = match output.rsplit_once('/') {
+            Some((dir, _)) => format!("{dir}/{}", cmd.library_name),
+            None => cmd.library_name.clone(),
+        };
+        let is_library = |file: &str| {
+            file == output || (file.starts_with(&shard_prefix) && file.ends_with(".s.sol"))
+        };
+        let mut hashes = BTreeMap::new();
+        for (ident, contract) in source_variants(&contracts, &options)? {
+            if !contract.kind.is_deployable() {
+                continue;
+            }
+            if !contract.constructor_params.is_empty() {
+                warning!(
+                    "{} takes constructor arguments, which its init code hash would leave out, initCodeHash reverts for it",
+                    ident
+                );
+                continue;
+            }
+            if graph.depends_on(&paths.relative_to_root(&contract.path), is_library) {
+                warning!(
+                    "{} imports the reflection library, so its init code hash would depend on itself, initCodeHash reverts for it",
+                    ident
+                );
+                continue;
+            }
+            match index.init_code_hash(contract)? {
+                Some(hash) => {
+                    hashes.insert(ident, hash);
+                }
+                None => warning!(
+                    "{} has no fixed creation code in the artifacts, initCodeHash reverts for it",
+                    ident
+                ),
+            }
+        }
+        options.init_code_hashes = Some(hashes);
+    }
+
+    let output_path = paths.root.join(&output);
+
+    // Keep the ordinals of existing variants, adopting those of a library generated before the
+    // lockfile existed. Only an unsharded library has them in its `Source` enum, so an existing
+    // dispatcher without the lockfile is rejected rather than silently renumbered.
+    let lock_path = paths.root.join(LOCKFILE_PATH);
+    let mut lock = match SourceLock::load(&lock_path)? {
+        Some(lock) => lock,
+        None => match fs::read_to_string(&output_path).ok() {
+            Some(library) if library.contains("type Source is uint256;") => {
+                return Err(GeneratorError::UnrecoverableOrdinals(output).into());
+            }
+            Some(library) => SourceLock::from_library(&library).unwrap_or_default(),
+            None => SourceLock::default(),
+        },
     };
+    lock.update(&variant_names(&contracts, &options)?)?;
+    lock.assign_shards(&variant_shards(&contracts, &options)?);
+    options.lock = Some(lock.clone());
 
-    let output_code = generate_sources_library(&contracts, &options);
+    // The dispatcher goes to the output file, the shard libraries next to it
+    let output_dir = output.rsplit_once('/').map(|(dir, _)| dir);
+    let mut files: Vec<(String, String)> = generate_libraries(&contracts, &options)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, library)| {
+            let file = match output_dir {
+                _ if i == 0 => output.clone(),
+                Some(dir) => format!("{dir}/{}.s.sol", library.library_name),
+                None => format!("{}.s.sol", library.library_name),
+            };
+            (file, library.code)
+        })
+        .collect();
+    if files.len() > 1 {
+        remark!("Sharded the Source enum over {} libraries", files.len() - 1);
+    }
+
+    // The libraries do not compile unless every path they import resolves
+    let resolver = ImportResolver::new(paths)?;
+    for (file, code) in &files {
+        if let Ok(generated) = parse_source_unit(code) {
+            for diagnostic in resolver.check(file, &generated.imports) {
+                warning!("{}", diagnostic);
+            }
+        }
+    }
+
+    if cmd.smoke_tests || config.smoke_tests {
+        let di_dir = paths.relative_to_root(paths.script.join("reflections/di"));
+        let wiring = format!("{di_dir}/wiring/StdConfigBasedWiring.s.sol");
+        // The wiring imports `Sources` from the scaffolded libraries directory, so the tests
+        // only compile against the library generated there
+        let wired_library = format!("{di_dir}/libraries/Sources.s.sol");
+        if output != wired_library || cmd.library_name != "Sources" {
+            warning!(
+                "Skipping the smoke tests, StdConfigBasedWiring deploys the Sources library at {}",
+                wired_library
+            );
+        } else if !paths.root.join(&wiring).is_file() {
+            warning!("Skipping the smoke tests, run `reflections init` to scaffold {}", wiring);
+        } else {
+            let graph = ImportGraph::from_dir(&format!("{di_dir}/wiring"), &resolver);
+            let mut unresolved: Vec<_> = graph
+                .diagnostics
+                .iter()
+                .filter_map(|diagnostic| match diagnostic {
+                    // the library itself is not there before the first generation
+                    ImportError::Unresolved { file, import, .. }
+                        if file.starts_with(&di_dir) &&
+                            !import.ends_with("libraries/Sources.s.sol") =>
+                    {
+                        Some(import.as_str())
+                    }
+                    _ => None,
+                })
+                .collect();
+            unresolved.sort_unstable();
+            unresolved.dedup();
+            if !unresolved.is_empty() {
+                warning!(
+                    "The smoke tests do not compile until these imports of StdConfigBasedWiring resolve: {}",
+                    unresolved.join(", ")
+                );
+            }
 
-    let output_path = paths.root.join(&cmd.output);
+            let file = paths.relative_to_root(
+                paths.test.join(format!("reflections/{}.t.sol", cmd.library_name)),
+            );
+            remark!("Generating smoke tests at {}", file);
+            files.push((file, generate_smoke_tests(&contracts, &options, &di_dir)?));
+        }
+    }
 
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)?;
+    if cmd.check {
+        let mut outdated = None;
+        for (file, code) in &files {
+            let current = fs::read_to_string(paths.root.join(file)).unwrap_or_default();
+            if let Some(diff) = diff_library(file, &current, code) {
+                // next to the log, which cliclack writes to stderr
+                eprint!("{diff}");
+                let stale = stale_init_code_hashes(&current, code);
+                if !stale.is_empty() {
+                    warning!("Stale init code hashes in {}: {}", file, stale.join(", "));
+                }
+                outdated.get_or_insert_with(|| file.clone());
+            }
+        }
+        // A stale or missing lockfile would renumber the variants of the next generation
+        let current = fs::read_to_string(&lock_path).unwrap_or_default();
+        if let Some(diff) = diff_library(LOCKFILE_PATH, &current, &lock.to_toml()?) {
+            eprint!("{diff}");
+            outdated.get_or_insert_with(|| LOCKFILE_PATH.to_string());
+        }
+        if let Some(file) = outdated {
+            return Err(GeneratorError::OutdatedOutput(file).into());
+        }
+        success!("Reflection library at {} is up to date", output);
+        return Ok(());
     }
 
-    fs::write(&output_path, output_code)?;
+    for (file, code) in &files {
+        let path = paths.root.join(file);
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, code)?;
+    }
+    lock.save(&lock_path)?;
 
-    success!("Generated reflection library at: {}", cmd.output);
+    success!("Generated reflection library at: {}", output);
 
     Ok(())
 }
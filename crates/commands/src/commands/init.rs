@@ -1,8 +1,10 @@
-use crate::utils::{remark, success};
+use crate::utils::{remark, success, warning};
 use clap::Parser;
 use reflections_core::{
     Result,
     config::{Paths, ReflectionsConfig},
+    errors::ImportError,
+    imports::{ImportGraph, ImportResolver},
     remappings::Remappings,
     utils::{copy_dir_with_remappings, get_assets_dir},
 };
@@ -44,14 +46,17 @@ pub(crate) async fn init_command(paths: &Paths, cmd: Init) -> Result<()> {
     config.save(&reflections_config_path)?;
     remark!("Updated reflections.toml with configuration");
 
+    // The DI framework lives in the scripts directory (`script` in foundry.toml)
+    let script_dir = paths.relative_to_root(&paths.script);
+
     if cmd.clean {
         remark!("Flag `--clean` was set, cleaning project");
         
         // Remove previous DI framework scaffolding
-        let reflections_dir = paths.root.join("scripts").join("reflections");
+        let reflections_dir = paths.script.join("reflections");
         if reflections_dir.exists() {
             fs::remove_dir_all(&reflections_dir)?;
-            remark!("Removed previous scripts/reflections/ directory");
+            remark!("Removed previous {}/reflections/ directory", script_dir);
         }
     }
 
@@ -66,13 +71,17 @@ pub(crate) async fn init_command(paths: &Paths, cmd: Init) -> Result<()> {
         )));
     }
 
-    let target_dir = paths.root.join("scripts").join("reflections").join("di");
-    remark!("Copying DI framework to scripts/reflections/di/...");
+    let target_dir = paths.script.join("reflections").join("di");
+    remark!("Copying DI framework to {}/reflections/di/...", script_dir);
 
     // Load or create default remappings
-    let remappings = if paths.remappings.exists() {
-        remark!("Loading remappings from {}", paths.remappings.display());
-        Remappings::from_file(&paths.remappings)?
+    let remappings = if paths.remappings.exists() || !paths.config_remappings.is_empty() {
+        if paths.remappings.exists() {
+            remark!("Loading remappings from {}", paths.remappings.display());
+        } else {
+            remark!("Loading remappings from foundry.toml");
+        }
+        Remappings::load(paths)?
     } else {
         remark!("No remappings.txt found, using default mappings");
         let mut remappings = Remappings::new();
@@ -87,14 +96,41 @@ pub(crate) async fn init_command(paths: &Paths, cmd: Init) -> Result<()> {
     copy_dir_with_remappings(&solidity_assets, &target_dir, &remappings)?;
     success!("DI framework scaffolded successfully!");
 
+    // Check that the scaffolded imports resolve in this project
+    let scaffold_dir = paths.relative_to_root(&target_dir);
+    let graph = ImportGraph::from_dir(&scaffold_dir, &ImportResolver::new(paths)?);
+    let mut unresolved: Vec<_> = graph
+        .diagnostics
+        .iter()
+        .filter_map(|diagnostic| match diagnostic {
+            ImportError::Unresolved { file, import, .. } if file.starts_with(&scaffold_dir) => {
+                Some(import.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    unresolved.sort_unstable();
+    unresolved.dedup();
+    if !unresolved.is_empty() {
+        warning!(
+            "{} imports of the DI framework do not resolve, install the missing dependencies or add remappings:",
+            unresolved.len()
+        );
+        for import in unresolved {
+            remark!("  - {}", import);
+        }
+    }
+
     // Update .gitignore using template
     let gitignore_template_path = assets_dir.join(".gitignoreTemplate");
     let gitignore_template = if gitignore_template_path.exists() {
         fs::read_to_string(&gitignore_template_path)?
     } else {
         // Fallback if template is missing
-        "# Reflections - DI Framework\n# The entire scripts/reflections/di/ directory is scaffolded by `reflections init`\n# and should not be committed to version control\n/scripts/reflections/\n".to_string()
+        "# Reflections - DI Framework\n# The entire scripts/reflections/di/ directory is scaffolded by `reflections init`\n# and should not be committed to version control\n/scripts/reflections/\n/.reflections/cache/\n".to_string()
     };
+    let gitignore_template =
+        gitignore_template.replace("scripts/reflections/", &format!("{script_dir}/reflections/"));
 
     let gitignore_path = paths.root.join(".gitignore");
     if gitignore_path.exists() {
@@ -2,9 +2,11 @@ pub use clap::{Parser, Subcommand};
 use clap_verbosity_flag::{LogLevel, VerbosityFilter};
 use derive_more::derive::From;
 
+pub mod addresses;
 pub mod collect;
 pub mod generate;
 pub mod init;
+pub mod selectors;
 pub mod serve;
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -60,6 +62,8 @@ pub enum Command {
     Generate(generate::Generate),
     Collect(collect::Collect),
     Serve(serve::Serve),
+    Addresses(addresses::Addresses),
+    Selectors(selectors::Selectors),
     Version(Version),
 }
 
@@ -0,0 +1,151 @@
+use crate::{
+    commands::collect::{ABICollection, CollectedABI},
+    utils::{remark, success, warning},
+};
+use clap::Parser;
+use reflections_core::{
+    ReflectionsError, Result,
+    config::Paths,
+    parser::{ContractKind, discover_contracts},
+    selectors::{SelectorsContract, SelectorsOptions, generate_selectors_library, interface_id},
+};
+use std::fs;
+
+/// Generate a library of selectors, event topics and error selectors from collected ABIs
+#[derive(Debug, Clone, Parser, bon::Builder)]
+#[allow(clippy::duplicated_attributes)]
+#[builder(on(String, into))]
+#[clap(after_help = "For more information, read the README.md")]
+#[non_exhaustive]
+pub struct Selectors {
+    /// Path to the collected ABIs JSON file
+    #[arg(short, long, default_value = "abis.json")]
+    #[builder(default)]
+    pub input: String,
+
+    /// Output file for the generated library
+    ///
+    /// Defaults to `reflections/Selectors.s.sol` inside `script` from foundry.toml.
+    #[arg(short, long)]
+    pub output: Option<String>,
+
+    /// Name of the generated library
+    #[arg(long, default_value = "Selectors")]
+    #[builder(default)]
+    pub library_name: String,
+
+    /// SPDX license identifier
+    #[arg(long, default_value = "MIT")]
+    #[builder(default)]
+    pub license: String,
+
+    /// Solidity version pragma
+    #[arg(long, default_value = "^0.8.0")]
+    #[builder(default)]
+    pub solidity_version: String,
+
+    /// Path to the contracts directory, used to find the interfaces and their parents
+    ///
+    /// Defaults to `src` from foundry.toml. Interface ids are only generated for the interfaces
+    /// declared in it.
+    #[arg(short, long)]
+    pub contracts_dir: Option<String>,
+}
+
+pub(crate) async fn selectors_command(paths: &Paths, cmd: Selectors) -> Result<()> {
+    let input_path = paths.root.join(&cmd.input);
+    if !input_path.exists() {
+        return Err(ReflectionsError::IOError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Collected ABIs not found: {}. Run 'reflections collect' first.", cmd.input),
+        )));
+    }
+    let collection: ABICollection = serde_json::from_str(&fs::read_to_string(&input_path)?)
+        .map_err(|e| {
+            ReflectionsError::IOError(std::io::Error::other(format!(
+                "Failed to parse collected ABIs in {}: {e}",
+                cmd.input
+            )))
+        })?;
+
+    // artifacts of contracts compiled with several compilers are named `<Contract>.<version>`
+    let mut abis: Vec<(&str, &CollectedABI)> = collection
+        .grouped
+        .values()
+        .flatten()
+        .chain(&collection.ungrouped)
+        .map(|abi| (abi.contract_name.split('.').next().unwrap_or_default(), abi))
+        .collect();
+    abis.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.file_path.cmp(&y.file_path)));
+    abis.dedup_by(|(name, abi), (kept_name, kept)| {
+        if name != kept_name {
+            return false;
+        }
+        if abi.abi != kept.abi {
+            warning!("{} is declared more than once, skipping {}", name, abi.file_path);
+        }
+        true
+    });
+    remark!("Read {} ABIs from {}", abis.len(), cmd.input);
+
+    let contracts_dir = cmd.contracts_dir.unwrap_or_else(|| paths.relative_to_root(&paths.src));
+    let declared = if paths.root.join(&contracts_dir).is_dir() {
+        remark!("Resolving interfaces from {}", contracts_dir);
+        discover_contracts(&paths.root, &contracts_dir).unwrap_or_else(|e| {
+            warning!(
+                "Could not resolve the interfaces in {}: {}, skipping interface ids",
+                contracts_dir,
+                e
+            );
+            Vec::new()
+        })
+    } else {
+        remark!("No contracts directory {}, skipping interface ids", contracts_dir);
+        Vec::new()
+    };
+
+    let contracts: Vec<SelectorsContract> = abis
+        .iter()
+        .map(|(name, collected)| {
+            let interface =
+                declared.iter().find(|c| c.name == *name && c.kind == ContractKind::Interface);
+            let interface_id = interface.and_then(|interface| {
+                // the id leaves out inherited functions, so every parent's ABI is needed
+                let inherited: Option<Vec<_>> = interface
+                    .bases
+                    .iter()
+                    .map(|base| {
+                        abis.iter()
+                            .find(|(name, _)| *name == base.contract_name())
+                            .map(|(_, abi)| &abi.abi)
+                    })
+                    .collect();
+                if inherited.is_none() {
+                    warning!("Missing the ABI of a parent of {}, skipping its interface id", name);
+                }
+                Some(interface_id(&collected.abi, &inherited?))
+            });
+            SelectorsContract { name: name.to_string(), abi: collected.abi.clone(), interface_id }
+        })
+        .collect();
+
+    let options = SelectorsOptions {
+        library_name: cmd.library_name,
+        license: cmd.license,
+        solidity_version: cmd.solidity_version,
+    };
+    let code = generate_selectors_library(&contracts, &options);
+
+    let output = cmd.output.unwrap_or_else(|| {
+        paths.relative_to_root(paths.script.join("reflections/Selectors.s.sol"))
+    });
+    let output_path = paths.root.join(&output);
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&output_path, code)?;
+
+    success!("Generated selectors library at: {}", output);
+
+    Ok(())
+}
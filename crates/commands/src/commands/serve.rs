@@ -111,9 +111,9 @@ struct OpenAPISchema {
     #[serde(rename = "type")]
     schema_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    items: Option<Box<OpenAPISchema>>,
+    items: Option<Box<Self>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    properties: Option<HashMap<String, OpenAPISchema>>,
+    properties: Option<HashMap<String, Self>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -55,6 +55,72 @@ impl From<reflections_core::config::ConfigLocation> for ConfigLocation {
     }
 }
 
+/// Which discovered contracts become variants of the generated `Source` enum.
+///
+/// This is a new type so we can implement the `ValueEnum` trait for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, FromStr)]
+pub struct EnumPolicy(reflections_core::generator::EnumPolicy);
+
+impl clap::ValueEnum for EnumPolicy {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self(reflections_core::generator::EnumPolicy::All),
+            Self(reflections_core::generator::EnumPolicy::Deployable),
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self.0 {
+            reflections_core::generator::EnumPolicy::All => PossibleValue::new("all"),
+            reflections_core::generator::EnumPolicy::Deployable => PossibleValue::new("deployable"),
+        })
+    }
+}
+
+impl From<EnumPolicy> for reflections_core::generator::EnumPolicy {
+    fn from(value: EnumPolicy) -> Self {
+        value.0
+    }
+}
+
+impl From<reflections_core::generator::EnumPolicy> for EnumPolicy {
+    fn from(value: reflections_core::generator::EnumPolicy) -> Self {
+        Self(value)
+    }
+}
+
+/// How the `Source` enum is split over several libraries.
+///
+/// This is a new type so we can implement the `ValueEnum` trait for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, FromStr)]
+pub struct ShardBy(reflections_core::generator::Sharding);
+
+impl clap::ValueEnum for ShardBy {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self(reflections_core::generator::Sharding::None),
+            Self(reflections_core::generator::Sharding::Directory),
+            Self(reflections_core::generator::Sharding::Count),
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(PossibleValue::new(self.0.as_str()))
+    }
+}
+
+impl From<ShardBy> for reflections_core::generator::Sharding {
+    fn from(value: ShardBy) -> Self {
+        value.0
+    }
+}
+
+impl From<reflections_core::generator::Sharding> for ShardBy {
+    fn from(value: reflections_core::generator::Sharding) -> Self {
+        Self(value)
+    }
+}
+
 pub async fn run(command: Command, verbosity: Verbosity<CustomLevel>) -> Result<()> {
     if env::var("RUST_LOG").is_ok() {
         env_logger::builder().try_init().ok(); // init logger if possible (not already initialized)
@@ -135,6 +201,36 @@ pub async fn run(command: Command, verbosity: Verbosity<CustomLevel>) -> Result<
             })?;
             outro!("Done serving!");
         }
+        Command::Addresses(cmd) => {
+            intro!("✨ Reflections Addresses ✨");
+            step!("Predict CREATE2 addresses of the sources");
+            // Use current dir as root unless specified by env
+            let root = env::var("REFLECTIONS_PROJECT_ROOT")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map_or(env::current_dir()?, PathBuf::from);
+
+            let paths = Paths::with_root_and_config(&root, None)?;
+            commands::addresses::addresses_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred while predicting addresses");
+            })?;
+            outro!("Done predicting addresses!");
+        }
+        Command::Selectors(cmd) => {
+            intro!("✨ Reflections Selectors ✨");
+            step!("Generate selectors, event topics and error selectors");
+            // Use current dir as root unless specified by env
+            let root = env::var("REFLECTIONS_PROJECT_ROOT")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map_or(env::current_dir()?, PathBuf::from);
+
+            let paths = Paths::with_root_and_config(&root, None)?;
+            commands::selectors::selectors_command(&paths, cmd).await.inspect_err(|_| {
+                outro_cancel!("An error occurred during generation");
+            })?;
+            outro!("Done generating selectors!");
+        }
         Command::Version(_) => {
             const VERSION: &str = env!("CARGO_PKG_VERSION");
             println!("reflections {VERSION}");
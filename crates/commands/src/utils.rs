@@ -82,9 +82,23 @@ macro_rules! success {
     };
 }
 
+macro_rules! warning {
+    ($msg:expr) => {
+        if $crate::TUI_ENABLED.load(::std::sync::atomic::Ordering::Relaxed) {
+            ::cliclack::log::warning($msg).ok();
+        }
+    };
+    ($fmt:expr, $($arg:expr),+) => {
+        if $crate::TUI_ENABLED.load(::std::sync::atomic::Ordering::Relaxed) {
+            ::cliclack::log::warning(format!($fmt, $($arg),+)).ok();
+        }
+    };
+}
+
 pub(crate) use intro;
 pub(crate) use outro;
 pub(crate) use outro_cancel;
 pub(crate) use remark;
 pub(crate) use step;
 pub(crate) use success;
+pub(crate) use warning;
@@ -0,0 +1,293 @@
+use clap::Parser;
+use reflections_commands::{Command, Verbosity, commands::addresses::Addresses, run};
+use reflections_core::create2::{Address, SaltNamespace, create2_address, decode_hex, keccak256};
+use std::fs;
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+const DEPLOYER: &str = "0x4e59b44847b379578588920cA78FbF26c0B4956C";
+const CREATION_CODE: &str = "6080604052348015600e575f80fd5b50";
+
+fn write_artifacts(dir: &std::path::Path) {
+    let artifact = |source: &str, name: &str, kind: &str, bytecode: &str| {
+        let abi = if name == "Escrow" {
+            r#"[{"type": "constructor", "inputs": [{"name": "owner", "type": "address", "internalType": "address"}], "stateMutability": "nonpayable"}]"#
+        } else {
+            "[]"
+        };
+        format!(
+            r#"{{"abi": {abi}, "bytecode": {{"object": "{bytecode}"}}, "ast": {{"absolutePath": "{source}", "nodes": [{{"nodeType": "ContractDefinition", "name": "{name}", "abstract": false, "contractKind": "{kind}", "baseContracts": []}}]}}}}"#
+        )
+    };
+    for (source, name, kind, bytecode) in [
+        ("src/Vault.sol", "Vault", "contract", format!("0x{CREATION_CODE}")),
+        ("src/IVault.sol", "IVault", "interface", "0x".to_string()),
+        ("src/Escrow.sol", "Escrow", "contract", format!("0x{CREATION_CODE}")),
+        ("src/Linked.sol", "Linked", "contract", format!("0x6080{}", "__$0123456789abcdef$__")),
+    ] {
+        let artifact_dir = dir.join("out").join(source.trim_start_matches("src/"));
+        fs::create_dir_all(&artifact_dir).expect("Failed to create artifact dir");
+        fs::write(
+            artifact_dir.join(format!("{name}.json")),
+            artifact(source, name, kind, &bytecode),
+        )
+        .expect("Failed to write artifact");
+    }
+}
+
+fn expected(namespace: &SaltNamespace, nickname: Option<&str>) -> String {
+    let deployer: Address = DEPLOYER.parse().expect("Invalid deployer");
+    let init_code_hash = keccak256(&decode_hex(CREATION_CODE).expect("Invalid creation code"));
+    let salt = namespace.salt(Some(10), "Vault", nickname).expect("Invalid salt");
+    create2_address(&deployer, &salt, &init_code_hash).to_string()
+}
+
+/// The examples of EIP-1014, checking `create2_address` independently of `expected`.
+#[test]
+fn test_create2_eip1014_examples() {
+    let zero = "0000000000000000000000000000000000000000000000000000000000000000";
+    let examples = [
+        (
+            "0x0000000000000000000000000000000000000000",
+            zero,
+            "00",
+            "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38",
+        ),
+        (
+            "0xdeadbeef00000000000000000000000000000000",
+            zero,
+            "00",
+            "0xB928f69Bb1D91Cd65274e3c79d8986362984fDA3",
+        ),
+        (
+            "0xdeadbeef00000000000000000000000000000000",
+            "000000000000000000000000feed000000000000000000000000000000000000",
+            "00",
+            "0xD04116cDd17beBE565EB2422F2497E06cC1C9833",
+        ),
+        (
+            "0x0000000000000000000000000000000000000000",
+            zero,
+            "deadbeef",
+            "0x70f2b2914A2a4b783FaEFb75f459A580616Fcb5e",
+        ),
+        (
+            "0x00000000000000000000000000000000deadbeef",
+            "00000000000000000000000000000000000000000000000000000000cafebabe",
+            "deadbeef",
+            "0x60f3f640a8508fC6a86d45DF051962668E1e8AC7",
+        ),
+        (
+            "0x00000000000000000000000000000000deadbeef",
+            "00000000000000000000000000000000000000000000000000000000cafebabe",
+            "deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef",
+            "0x1d8bfDC5D46DC4f61D6b6115972536eBE6A8854C",
+        ),
+        (
+            "0x0000000000000000000000000000000000000000",
+            zero,
+            "",
+            "0xE33C0C7F7df4809055C3ebA6c09CFe4BaF1BD9e0",
+        ),
+    ];
+    for (deployer, salt, init_code, address) in examples {
+        let deployer: Address = deployer.parse().unwrap();
+        let salt: [u8; 32] = decode_hex(salt).unwrap().try_into().unwrap();
+        let init_code_hash = keccak256(&decode_hex(init_code).unwrap());
+        assert_eq!(create2_address(&deployer, &salt, &init_code_hash).to_string(), address);
+    }
+}
+
+#[tokio::test]
+async fn test_addresses_json() {
+    let dir = testdir!();
+    write_artifacts(&dir);
+
+    let cmd: Command = Addresses::builder()
+        .deployer(DEPLOYER)
+        .nickname(vec!["alice".to_string()])
+        .json(true)
+        .output("addresses.json")
+        .build()
+        .into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("addresses.json")).unwrap()).unwrap();
+    // the interface has no creation code, the linked contract no fixed one, and the constructor
+    // arguments of Escrow are part of its init code
+    let predicted = json.as_array().unwrap();
+    assert_eq!(predicted.len(), 2, "{json:#}");
+    assert_eq!(predicted[0]["source"], "Vault");
+    assert!(predicted[0]["nickname"].is_null());
+    assert_eq!(predicted[0]["address"], expected(&SaltNamespace::default(), None).as_str());
+    assert_eq!(predicted[1]["nickname"], "alice");
+    assert_eq!(
+        predicted[1]["address"],
+        expected(&SaltNamespace::default(), Some("alice")).as_str()
+    );
+    assert_eq!(predicted[1]["salt"].as_str().unwrap().len(), 66);
+}
+
+#[tokio::test]
+async fn test_addresses_table() {
+    let dir = testdir!();
+    write_artifacts(&dir);
+
+    let cmd: Command =
+        Addresses::builder().deployer(DEPLOYER).output("addresses.txt").build().into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let table = fs::read_to_string(dir.join("addresses.txt")).unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 2, "{table}");
+    assert!(lines[0].starts_with("SOURCE"));
+    assert!(lines[1].starts_with("Vault"));
+    assert!(lines[1].ends_with(&expected(&SaltNamespace::default(), None)));
+}
+
+#[tokio::test]
+async fn test_addresses_source_selection() {
+    let dir = testdir!();
+    write_artifacts(&dir);
+    fs::create_dir_all(dir.join("out/MockVault.sol")).unwrap();
+    fs::write(
+        dir.join("out/MockVault.sol/MockVault.json"),
+        format!(
+            r#"{{"abi": [], "bytecode": {{"object": "0x{CREATION_CODE}"}}, "ast": {{"absolutePath": "src/mocks/MockVault.sol", "nodes": [{{"nodeType": "ContractDefinition", "name": "MockVault", "abstract": false, "contractKind": "contract", "baseContracts": [{{"baseName": {{"name": "IVault"}}}}]}}]}}}}"#
+        ),
+    )
+    .unwrap();
+
+    let root = dir.to_string_lossy().to_string();
+    let sources = |cmd: Addresses| {
+        let root = root.clone();
+        let dir = dir.clone();
+        async move {
+            let res = async_with_vars(
+                [("REFLECTIONS_PROJECT_ROOT", Some(root.as_str()))],
+                run(cmd.into(), Verbosity::default()),
+            )
+            .await;
+            assert!(res.is_ok(), "{res:?}");
+            let json: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(dir.join("addresses.json")).unwrap())
+                    .unwrap();
+            json.as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["source"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    let cmd = || Addresses::builder().deployer(DEPLOYER).json(true).output("addresses.json");
+
+    assert_eq!(sources(cmd().build()).await, ["MockVault", "Vault"]);
+    // the same selection flags as `generate`
+    assert_eq!(
+        sources(cmd().inherits_from(vec!["IVault".to_string()]).build()).await,
+        ["MockVault"]
+    );
+    assert_eq!(sources(cmd().exclude(vec!["mocks/**".to_string()]).build()).await, ["Vault"]);
+    assert_eq!(sources(cmd().include(vec!["mocks/**".to_string()]).build()).await, ["MockVault"]);
+
+    // the command line overrides the filters of reflections.toml
+    fs::write(dir.join("reflections.toml"), "[generate]\nexclude = [\"mocks/**\"]\n").unwrap();
+    assert_eq!(sources(cmd().build()).await, ["Vault"]);
+    assert_eq!(sources(cmd().exclude(vec!["Vault.sol".to_string()]).build()).await, ["MockVault"]);
+}
+
+#[tokio::test]
+async fn test_addresses_invalid_input() {
+    let dir = testdir!();
+    write_artifacts(&dir);
+
+    for cmd in [
+        Addresses::builder().deployer("0x1234").build(),
+        Addresses::builder().deployer(DEPLOYER).nickname(vec!["a".repeat(32)]).build(),
+    ] {
+        let res = async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+        .await;
+        assert!(res.is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_addresses_salt_namespace() {
+    let dir = testdir!();
+    write_artifacts(&dir);
+    fs::write(
+        dir.join("reflections.toml"),
+        "[generate.salt-namespace]\nproject = \"acme\"\nversion = \"v2\"\nchain-id = true\n",
+    )
+    .unwrap();
+
+    let root = dir.to_string_lossy().to_string();
+    let run_with = |cmd: Addresses| {
+        async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(root.as_str()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+    };
+
+    // the chain is part of the salt
+    assert!(run_with(Addresses::builder().deployer(DEPLOYER).build()).await.is_err());
+
+    let cmd = Addresses::builder()
+        .deployer(DEPLOYER)
+        .chain_id(10)
+        .salt_version("v3")
+        .json(true)
+        .output("addresses.json")
+        .build();
+    let res = run_with(cmd).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("addresses.json")).unwrap()).unwrap();
+    let namespace =
+        SaltNamespace { project: "acme".to_string(), version: "v3".to_string(), chain_id: true };
+    assert_eq!(json[0]["address"], expected(&namespace, None).as_str());
+    assert_ne!(json[0]["address"], expected(&SaltNamespace::default(), None).as_str());
+
+    // the command line turns the configured chain id off
+    let cmd = Addresses::builder()
+        .deployer(DEPLOYER)
+        .salt_chain_id(false)
+        .json(true)
+        .output("addresses.json")
+        .build();
+    let res = run_with(cmd).await;
+    assert!(res.is_ok(), "{res:?}");
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("addresses.json")).unwrap()).unwrap();
+    let namespace =
+        SaltNamespace { project: "acme".to_string(), version: "v2".to_string(), chain_id: false };
+    assert_eq!(json[0]["address"], expected(&namespace, None).as_str());
+}
+
+#[test]
+fn test_addresses_salt_chain_id_flag() {
+    let parse = |args: &[&str]| {
+        Addresses::try_parse_from([&["addresses", "--deployer", DEPLOYER], args].concat())
+            .unwrap()
+            .salt_chain_id
+    };
+    assert_eq!(parse(&[]), None);
+    assert_eq!(parse(&["--salt-chain-id"]), Some(true));
+    assert_eq!(parse(&["--salt-chain-id", "false"]), Some(false));
+    assert_eq!(parse(&["--salt-chain-id=true"]), Some(true));
+}
@@ -1,4 +1,8 @@
-use reflections_commands::{Command, Verbosity, commands::collect::Collect, run};
+use reflections_commands::{
+    Command, Verbosity,
+    commands::collect::{Collect, GroupBy},
+    run,
+};
 use std::fs;
 use temp_env::async_with_vars;
 use testdir::testdir;
@@ -240,3 +244,172 @@ async fn test_collect_custom_output_path() {
     assert!(res.is_ok(), "{res:?}");
     assert!(dir.join("custom-abis.json").exists());
 }
+
+#[tokio::test]
+async fn test_collect_group_by_base() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(
+        src_dir.join("Modules.sol"),
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IModule {}
+abstract contract Module is IModule {}
+contract VaultModule is IModule, Module {}
+contract Plugin is IModule {}
+contract Standalone {}
+"#,
+    )
+    .unwrap();
+
+    let artifact = r#"{
+        "abi": [
+            {
+                "type": "function",
+                "name": "run",
+                "inputs": [],
+                "outputs": [],
+                "stateMutability": "nonpayable"
+            }
+        ]
+    }"#;
+    for name in ["VaultModule", "Plugin", "Standalone"] {
+        let contract_dir = dir.join("out").join("Modules.sol");
+        fs::create_dir_all(&contract_dir).unwrap();
+        fs::write(contract_dir.join(format!("{name}.json")), artifact).unwrap();
+    }
+
+    let mut cmd = collect_cmd();
+    cmd.group_by = GroupBy::Base;
+    cmd.contracts_dir = Some("src".to_string());
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let content = fs::read_to_string(dir.join("abis.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["grouped"]["Module"][0]["contract_name"], "VaultModule");
+    assert_eq!(json["grouped"]["IModule"][0]["contract_name"], "Plugin");
+    assert_eq!(json["ungrouped"][0]["contract_name"], "Standalone");
+}
+
+#[tokio::test]
+async fn test_collect_foundry_out_dir() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    fs::write(dir.join("foundry.toml"), "[profile.default]\nout = \"build\"\n").unwrap();
+    let contract_dir = dir.join("build/Counter.sol");
+    fs::create_dir_all(&contract_dir).unwrap();
+    fs::write(
+        contract_dir.join("Counter.json"),
+        r#"{"abi": [{"type": "function", "name": "increment", "inputs": [], "outputs": [], "stateMutability": "nonpayable"}]}"#,
+    )
+    .unwrap();
+
+    let cmd = Collect::builder().output("abis.json".to_string()).tag("@custom:swagger".to_string()).build();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())), ("FOUNDRY_PROFILE", None)],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let abis = fs::read_to_string(dir.join("abis.json")).unwrap();
+    assert!(abis.contains("Counter"));
+}
+
+#[tokio::test]
+async fn test_collect_natspec_from_source() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(
+        src_dir.join("Vault.sol"),
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+/// @title Vault
+/// @custom:swagger Core
+contract Vault {}
+
+contract Helper {}
+"#,
+    )
+    .unwrap();
+
+    // Artifacts built without metadata
+    let artifact = r#"{"abi": [{"type": "function", "name": "run", "inputs": [], "outputs": [], "stateMutability": "nonpayable"}]}"#;
+    let contract_dir = dir.join("out").join("Vault.sol");
+    fs::create_dir_all(&contract_dir).unwrap();
+    for name in ["Vault", "Helper"] {
+        fs::write(contract_dir.join(format!("{name}.json")), artifact).unwrap();
+    }
+
+    let mut cmd = collect_cmd();
+    cmd.contracts_dir = Some("src".to_string());
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let content = fs::read_to_string(dir.join("abis.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["grouped"]["Core"][0]["contract_name"], "Vault");
+    assert_eq!(json["ungrouped"][0]["contract_name"], "Helper");
+}
+
+#[tokio::test]
+async fn test_collect_unparsable_source() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/Broken.sol"), "contract Broken {").unwrap();
+
+    // Artifacts with metadata do not need the sources
+    let artifact = r#"{
+        "abi": [{"type": "function", "name": "run", "inputs": [], "outputs": [], "stateMutability": "nonpayable"}],
+        "metadata": "{\"output\":{\"devdoc\":{\"custom\":{\"swagger\":\"Core\"}},\"userdoc\":{}}}"
+    }"#;
+    let contract_dir = dir.join("out").join("Counter.sol");
+    fs::create_dir_all(&contract_dir).unwrap();
+    fs::write(contract_dir.join("Counter.json"), artifact).unwrap();
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(collect_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let content = fs::read_to_string(dir.join("abis.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["grouped"]["Core"][0]["contract_name"], "Counter");
+
+    // nor do artifacts without the tag, which stay ungrouped
+    fs::write(
+        contract_dir.join("Counter.json"),
+        artifact.replace("swagger", "other"),
+    )
+    .unwrap();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(collect_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    let content = fs::read_to_string(dir.join("abis.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["ungrouped"][0]["contract_name"], "Counter");
+}
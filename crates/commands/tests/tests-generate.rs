@@ -1,4 +1,5 @@
 use reflections_commands::{Command, Verbosity, commands::generate::Generate, run};
+use reflections_core::create2::{encode_hex, keccak256};
 use std::fs;
 use temp_env::async_with_vars;
 use testdir::testdir;
@@ -391,3 +392,891 @@ async fn test_generate_contract_name_sanitization() {
     // Should handle the contract properly
     assert!(sources.contains("MyContract"));
 }
+
+#[tokio::test]
+async fn test_generate_ignores_commented_out_contracts() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+
+    fs::write(
+        src_dir.join("Commented.sol"),
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+/*
+contract Phantom {}
+*/
+// contract AnotherPhantom {}
+abstract contract Base {} contract Real is Base {
+    string public constant NOTE = "contract StringPhantom {";
+}
+"#,
+    )
+    .unwrap();
+
+    let cmd: Command = generate_cmd().into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("Source.Real"));
+    assert!(sources.contains("Source.Base"));
+    assert!(!sources.contains("Phantom"));
+}
+
+#[tokio::test]
+async fn test_generate_reports_unparsable_file() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+
+    fs::write(src_dir.join("Broken.sol"), "contract Broken {\n    function f() public {\n}")
+        .unwrap();
+
+    let cmd: Command = generate_cmd().into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    let err = res.unwrap_err().to_string();
+    assert!(err.contains("Broken.sol"), "{err}");
+}
+
+#[tokio::test]
+async fn test_generate_deployable_enum_policy() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+
+    fs::write(
+        src_dir.join("Mixed.sol"),
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IToken {}
+abstract contract Base {}
+library SafeMath {}
+contract RealContract is Base, IToken {}
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = generate_cmd();
+    cmd.enum_policy = "deployable".parse().unwrap();
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("type(_RealContract).creationCode"));
+    assert!(sources.contains("type(_SafeMath).creationCode"));
+    assert!(!sources.contains("Source.IToken"));
+    assert!(!sources.contains("Source.Base"));
+}
+
+#[tokio::test]
+async fn test_generate_inherits_from() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(src_dir.join("modules")).unwrap();
+
+    fs::write(
+        src_dir.join("Module.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\nabstract contract Module {}",
+    )
+    .unwrap();
+    fs::write(
+        src_dir.join("modules/Vault.sol"),
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import { Module } from "../Module.sol";
+
+abstract contract BaseVault is Module {}
+contract Vault is BaseVault {}
+contract Helper {}
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = generate_cmd();
+    cmd.inherits_from = vec!["Module".to_string()];
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("Source.Vault"));
+    assert!(sources.contains("Source.BaseVault"));
+    assert!(!sources.contains("Source.Helper"));
+    assert!(!sources.contains("Source.Module"));
+}
+
+#[tokio::test]
+async fn test_generate_duplicate_contract_names() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(src_dir.join("legacy")).unwrap();
+
+    let vault = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Vault {}";
+    fs::write(src_dir.join("Vault.sol"), vault).unwrap();
+    fs::write(src_dir.join("legacy/Vault.sol"), vault).unwrap();
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(generate_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    let err = res.unwrap_err().to_string();
+    assert!(err.contains("src/Vault.sol"), "{err}");
+    assert!(err.contains("src/legacy/Vault.sol"), "{err}");
+    assert!(!dir.join("scripts/reflections/Sources.s.sol").exists());
+
+    let mut cmd = generate_cmd();
+    cmd.disambiguate = true;
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains(r#"import { Vault as _Vault_legacy } from "src/legacy/Vault.sol";"#));
+    assert!(sources.contains("Source.Vault_legacy"));
+}
+
+#[tokio::test]
+async fn test_generate_include_exclude() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(src_dir.join("mocks")).unwrap();
+    fs::create_dir_all(src_dir.join("core")).unwrap();
+
+    let header = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\n";
+    fs::write(src_dir.join("core/Vault.sol"), format!("{header}contract Vault {{}}")).unwrap();
+    fs::write(src_dir.join("mocks/MockVault.sol"), format!("{header}contract MockVault {{}}"))
+        .unwrap();
+    fs::write(src_dir.join("Vault.t.sol"), format!("{header}contract VaultTest {{}}")).unwrap();
+    fs::write(src_dir.join("Deploy.s.sol"), format!("{header}contract Deploy {{}}")).unwrap();
+
+    let mut cmd = generate_cmd();
+    cmd.exclude = vec!["**/mocks/**".to_string()];
+    cmd.skip_tests = Some(true);
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("Source.Vault"));
+    assert!(sources.contains("Source.Deploy"));
+    assert!(!sources.contains("MockVault"));
+    assert!(!sources.contains("VaultTest"));
+
+    // patterns from reflections.toml apply when no flags are given
+    fs::write(
+        dir.join("reflections.toml"),
+        "[generate]\ninclude = [\"core/**\"]\nskip-scripts = true\n",
+    )
+    .unwrap();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(generate_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("Source.Vault"));
+    assert!(!sources.contains("Source.Deploy"));
+    assert!(!sources.contains("MockVault"));
+    assert!(!sources.contains("VaultTest"));
+
+    // and the flags win over them
+    let mut cmd = generate_cmd();
+    cmd.include = vec!["*.s.sol".to_string()];
+    cmd.skip_scripts = Some(false);
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("Source.Deploy"));
+}
+
+#[tokio::test]
+async fn test_generate_foundry_layout() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    fs::write(
+        dir.join("foundry.toml"),
+        "[profile.default]\nsrc = \"contracts\"\nscript = \"script\"\n\n[profile.v2]\nsrc = \"contracts-v2\"\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("contracts")).unwrap();
+    fs::create_dir_all(dir.join("contracts-v2")).unwrap();
+
+    let header = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\n";
+    fs::write(dir.join("contracts/Vault.sol"), format!("{header}contract Vault {{}}")).unwrap();
+    fs::write(dir.join("contracts-v2/VaultV2.sol"), format!("{header}contract VaultV2 {{}}"))
+        .unwrap();
+
+    // directories default to the active foundry.toml profile
+    let res = async_with_vars(
+        [
+            ("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("FOUNDRY_PROFILE", None),
+        ],
+        run(Generate::builder().build().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let output = dir.join("script/reflections/di/libraries/Sources.s.sol");
+    let sources = fs::read_to_string(&output).unwrap();
+    assert!(sources.contains(r#"import { Vault as _Vault } from "contracts/Vault.sol";"#));
+    assert!(!sources.contains("VaultV2"));
+
+    let res = async_with_vars(
+        [
+            ("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())),
+            ("FOUNDRY_PROFILE", Some("v2")),
+        ],
+        run(Generate::builder().build().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(&output).unwrap();
+    assert!(sources.contains(r#"import { VaultV2 as _VaultV2 } from "contracts-v2/VaultV2.sol";"#));
+    assert!(!sources.contains("Source.Vault,"));
+}
+
+#[tokio::test]
+async fn test_generate_pragma_compatibility() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(
+        src_dir.join("Vault.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.20;\n\ncontract Vault {}",
+    )
+    .unwrap();
+    fs::write(
+        src_dir.join("Math.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity >=0.8.0 <0.8.26;\n\nlibrary Math {}",
+    )
+    .unwrap();
+
+    // a conflicting pragma only warns by default
+    let mut cmd = generate_cmd();
+    cmd.solidity_version = "0.8.19".to_string();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.clone().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    cmd.strict_pragma = true;
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    let err = res.unwrap_err().to_string();
+    assert!(err.contains("pragma `0.8.19`"), "{err}");
+
+    let mut cmd = generate_cmd();
+    cmd.solidity_version = "auto".to_string();
+    cmd.strict_pragma = true;
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("pragma solidity >=0.8.20 <0.8.26;"));
+}
+
+#[tokio::test]
+async fn test_generate_discovery_cache() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let src_dir = dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::write(
+        src_dir.join("Counter.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Counter {}",
+    )
+    .unwrap();
+
+    let mut cmd = generate_cmd();
+    cmd.no_cache = true;
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(!dir.join(".reflections").exists());
+
+    let cold = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    for _ in 0..2 {
+        let res = async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+            run(generate_cmd().into(), Verbosity::default()),
+        )
+        .await;
+        assert!(res.is_ok(), "{res:?}");
+        assert!(dir.join(".reflections/cache/discovery.json").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap(),
+            cold
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_generate_dependencies() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    let header = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.20;\n\n";
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/Vault.sol"), format!("{header}contract Vault {{}}")).unwrap();
+
+    let proxy_dir = dir.join("lib/openzeppelin-contracts/contracts/proxy/ERC1967");
+    fs::create_dir_all(&proxy_dir).unwrap();
+    fs::write(
+        proxy_dir.join("ERC1967Proxy.sol"),
+        format!("{header}contract ERC1967Proxy {{}}\ncontract ERC1967Helper {{}}"),
+    )
+    .unwrap();
+    let beacon_dir = dir.join("lib/openzeppelin-contracts/contracts/proxy/beacon");
+    fs::create_dir_all(&beacon_dir).unwrap();
+    fs::write(beacon_dir.join("BeaconProxy.sol"), format!("{header}contract BeaconProxy {{}}"))
+        .unwrap();
+    fs::write(
+        dir.join("remappings.txt"),
+        "@openzeppelin/contracts/=lib/openzeppelin-contracts/contracts/\n",
+    )
+    .unwrap();
+
+    // remapped and raw `lib/` paths are both imported through the remapping
+    let mut cmd = generate_cmd();
+    cmd.dependency = vec![
+        "@openzeppelin/contracts/proxy/ERC1967/ERC1967Proxy.sol:ERC1967Proxy".to_string(),
+        "lib/openzeppelin-contracts/contracts/proxy/beacon/BeaconProxy.sol".to_string(),
+    ];
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains(
+        r#"import { ERC1967Proxy as _ERC1967Proxy } from "@openzeppelin/contracts/proxy/ERC1967/ERC1967Proxy.sol";"#
+    ));
+    assert!(sources.contains(
+        r#"import { BeaconProxy as _BeaconProxy } from "@openzeppelin/contracts/proxy/beacon/BeaconProxy.sol";"#
+    ));
+    assert!(sources.contains("Source.ERC1967Proxy"));
+    assert!(sources.contains("Source.Vault"));
+    assert!(!sources.contains("ERC1967Helper"));
+    assert!(!sources.contains("lib/openzeppelin-contracts"));
+
+    for dependency in
+        ["@missing/Proxy.sol", "@openzeppelin/contracts/proxy/beacon/BeaconProxy.sol:Nope"]
+    {
+        let mut cmd = generate_cmd();
+        cmd.dependency = vec![dependency.to_string()];
+        let res = async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+        .await;
+        assert!(res.is_err(), "{dependency} should fail");
+    }
+}
+
+#[tokio::test]
+async fn test_generate_from_artifacts() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+
+    // artifacts name contracts the parser never sees, the sources need not exist
+    let artifact = |source: &str, name: &str, kind: &str, bytecode: &str| {
+        format!(
+            r#"{{"abi": [], "bytecode": {{"object": "{bytecode}"}}, "ast": {{"absolutePath": "{source}", "nodes": [{{"nodeType": "ContractDefinition", "name": "{name}", "abstract": false, "contractKind": "{kind}", "baseContracts": []}}]}}}}"#
+        )
+    };
+    for (source, name, kind, bytecode) in [
+        ("src/Counter.sol", "Counter", "contract", "0x6080"),
+        ("src/ICounter.sol", "ICounter", "interface", "0x"),
+        ("test/Counter.t.sol", "CounterTest", "contract", "0x6080"),
+    ] {
+        let file = source.rsplit('/').next().unwrap();
+        let artifact_dir = dir.join("out").join(file);
+        fs::create_dir_all(&artifact_dir).unwrap();
+        fs::write(
+            artifact_dir.join(format!("{name}.json")),
+            artifact(source, name, kind, bytecode),
+        )
+        .unwrap();
+    }
+
+    let mut cmd = generate_cmd();
+    cmd.from_artifacts = true;
+    cmd.enum_policy = "deployable".parse().unwrap();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd.into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains(r#"import { Counter as _Counter } from "src/Counter.sol";"#));
+    assert!(!sources.contains("ICounter"));
+    assert!(!sources.contains("CounterTest"));
+    assert!(!dir.join(".reflections").exists());
+}
+
+#[tokio::test]
+async fn test_generate_stable_ordinals() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+
+    let header = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\n";
+    let write = |name: &str| {
+        fs::write(dir.join(format!("src/{name}.sol")), format!("{header}contract {name} {{}}"))
+            .unwrap();
+    };
+    let generate = || async {
+        let res = async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+            run(generate_cmd().into(), Verbosity::default()),
+        )
+        .await;
+        assert!(res.is_ok(), "{res:?}");
+        let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+        let start = sources.find("enum Source {").unwrap();
+        let end = start + sources[start..].find('}').unwrap();
+        sources[start..end]
+            .lines()
+            .skip(1)
+            .map(|line| line.split("//").next().unwrap().trim().trim_end_matches(','))
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    write("Token");
+    write("Vault");
+    assert_eq!(generate().await, ["NONE", "TransparentUpgradeableProxy", "Token", "Vault"]);
+    assert!(dir.join("reflections.lock").exists());
+
+    // new contracts are appended, removed ones leave a tombstone
+    write("Adapter");
+    fs::remove_file(dir.join("src/Token.sol")).unwrap();
+    assert_eq!(
+        generate().await,
+        ["NONE", "TransparentUpgradeableProxy", "Removed_Token", "Vault", "Adapter"]
+    );
+
+    // without a lockfile, the ordinals of the existing library are kept
+    fs::remove_file(dir.join("reflections.lock")).unwrap();
+    write("Token");
+    assert_eq!(
+        generate().await,
+        ["NONE", "TransparentUpgradeableProxy", "Token", "Vault", "Adapter"]
+    );
+
+    // a lockfile that would reorder the enum is rejected
+    let lock = fs::read_to_string(dir.join("reflections.lock")).unwrap();
+    fs::write(dir.join("reflections.lock"), lock.replace("ordinal = 3", "ordinal = 2")).unwrap();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(generate_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_generate_check() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/Token.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Token {}",
+    )
+    .unwrap();
+
+    let check_cmd = || {
+        Generate::builder()
+            .contracts_dir("src".to_string())
+            .output("scripts/reflections/Sources.s.sol".to_string())
+            .library_name("Sources".to_string())
+            .license("MIT".to_string())
+            .solidity_version("^0.8.0".to_string())
+            .check(true)
+            .build()
+    };
+    let root = dir.to_string_lossy().to_string();
+    let run_with = |cmd: Generate| {
+        async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(root.as_str()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+    };
+
+    // a missing library is out of date, and check mode does not create it
+    assert!(run_with(check_cmd()).await.is_err());
+    assert!(!dir.join("scripts/reflections/Sources.s.sol").exists());
+    assert!(!dir.join("reflections.lock").exists());
+
+    let res = run_with(generate_cmd()).await;
+    assert!(res.is_ok(), "{res:?}");
+    let res = run_with(check_cmd()).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // so does the lockfile, missing or stale
+    let lock = fs::read_to_string(dir.join("reflections.lock")).unwrap();
+    fs::remove_file(dir.join("reflections.lock")).unwrap();
+    assert!(run_with(check_cmd()).await.is_err());
+    assert!(!dir.join("reflections.lock").exists());
+    fs::write(dir.join("reflections.lock"), lock.replace("Token", "Coin")).unwrap();
+    assert!(run_with(check_cmd()).await.is_err());
+    fs::write(dir.join("reflections.lock"), lock).unwrap();
+    let res = run_with(check_cmd()).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // a new contract makes the committed library stale, which is left untouched
+    let before = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    fs::write(
+        dir.join("src/Vault.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Vault {}",
+    )
+    .unwrap();
+    assert!(run_with(check_cmd()).await.is_err());
+    let after = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert_eq!(before, after);
+}
+
+#[tokio::test]
+async fn test_generate_init_code_hashes() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/Counter.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Counter {}",
+    )
+    .unwrap();
+    let build = |bytecode: &str| {
+        fs::create_dir_all(dir.join("out/Counter.sol")).unwrap();
+        fs::write(
+            dir.join("out/Counter.sol/Counter.json"),
+            format!(
+                r#"{{"abi": [], "bytecode": {{"object": "{bytecode}"}}, "ast": {{"absolutePath": "src/Counter.sol", "nodes": [{{"nodeType": "ContractDefinition", "name": "Counter", "abstract": false, "contractKind": "contract", "baseContracts": []}}]}}}}"#
+            ),
+        )
+        .unwrap();
+    };
+
+    let root = dir.to_string_lossy().to_string();
+    let run_with = |check: bool| {
+        let mut cmd = generate_cmd();
+        cmd.init_code_hashes = true;
+        cmd.check = check;
+        async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(root.as_str()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+    };
+
+    // the hashes need the artifacts
+    assert!(run_with(false).await.is_err());
+
+    build("0x6080");
+    let res = run_with(false).await;
+    assert!(res.is_ok(), "{res:?}");
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains(&format!(
+        "if (metaArtifact == Source.Counter) {{\n            return 0x{};",
+        encode_hex(&keccak256(&[0x60, 0x80]))
+    )));
+    assert!(sources.contains("function predictAddress(Source metaArtifact, address deployer)"));
+    let res = run_with(true).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // rebuilt contracts make the hashes stale
+    build("0x6080604052");
+    assert!(run_with(true).await.is_err());
+    assert_eq!(fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap(), sources);
+
+    // a source importing the library has no hash, it would change with every generation
+    fs::write(
+        dir.join("src/Registry.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\nimport {Sources} from \"scripts/reflections/Sources.s.sol\";\n\ncontract Registry {}",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("out/Registry.sol")).unwrap();
+    fs::write(
+        dir.join("out/Registry.sol/Registry.json"),
+        r#"{"abi": [], "bytecode": {"object": "0x6080"}, "ast": {"absolutePath": "src/Registry.sol", "nodes": [{"nodeType": "ContractDefinition", "name": "Registry", "abstract": false, "contractKind": "contract", "baseContracts": []}]}}"#,
+    )
+    .unwrap();
+    let res = run_with(false).await;
+    assert!(res.is_ok(), "{res:?}");
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains(
+        "if (metaArtifact == Source.Registry) {\n            revert UnknownMetaArtifact();"
+    ));
+    assert!(sources.contains("if (metaArtifact == Source.Counter) {\n            return 0x"));
+}
+
+#[tokio::test]
+async fn test_generate_smoke_tests() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/Counter.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Counter {}\n\ncontract Vault {\n    constructor(address owner) {}\n}\n\ninterface ICounter {}",
+    )
+    .unwrap();
+    fs::write(dir.join("reflections.toml"), "[generate]\nsmoke-tests = true\n").unwrap();
+
+    let root = dir.to_string_lossy().to_string();
+    let run_with = |output: &str, check: bool| {
+        let mut cmd = generate_cmd();
+        cmd.output = Some(output.to_string());
+        cmd.check = check;
+        async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(root.as_str()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+    };
+    let output = "scripts/reflections/di/libraries/Sources.s.sol";
+
+    // the wiring only deploys the library it imports, from the scaffolded libraries directory
+    let res = run_with("scripts/reflections/Sources.s.sol", false).await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(!dir.join("test/reflections/Sources.t.sol").exists());
+
+    // and the wiring has to be scaffolded
+    let res = run_with(output, false).await;
+    assert!(res.is_ok(), "{res:?}");
+    assert!(!dir.join("test/reflections/Sources.t.sol").exists());
+
+    fs::create_dir_all(dir.join("scripts/reflections/di/wiring")).unwrap();
+    fs::write(
+        dir.join("scripts/reflections/di/wiring/StdConfigBasedWiring.s.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\nimport { Sources } from \"scripts/reflections/di/libraries/Sources.s.sol\";\n\ncontract StdConfigBasedWiring {}",
+    )
+    .unwrap();
+    let res = run_with(output, false).await;
+    assert!(res.is_ok(), "{res:?}");
+    let tests = fs::read_to_string(dir.join("test/reflections/Sources.t.sol")).unwrap();
+    assert!(
+        tests.contains(
+            "import { Sources } from \"scripts/reflections/di/libraries/Sources.s.sol\";"
+        )
+    );
+    assert!(tests.contains(
+        "import { StdConfigBasedWiring } from \"scripts/reflections/di/wiring/StdConfigBasedWiring.s.sol\";"
+    ));
+    assert!(
+        tests.contains("function test_Counter() public {\n        assertWired(Sources.Counter);")
+    );
+    assert!(
+        tests.contains(
+            "assertDeployed(Sources.Vault, Sources.toCreationCode(Sources.Vault, arg0));"
+        )
+    );
+    assert!(!tests.contains("ICounter"));
+    let res = run_with(output, true).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // the smoke tests are checked like the library
+    fs::write(dir.join("test/reflections/Sources.t.sol"), "").unwrap();
+    assert!(run_with(output, true).await.is_err());
+}
+
+#[tokio::test]
+async fn test_generate_custom_template() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/Token.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ncontract Token {}\n\ninterface IToken {}",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join(".reflections/templates")).unwrap();
+    fs::write(
+        dir.join(".reflections/templates/library.sol.j2"),
+        r#"// SPDX-License-Identifier: {{ options.license }}
+pragma solidity {{ options.solidity_version }};
+
+// generated from {{ paths.contracts_dir }} into {{ paths.output }}
+{{ sections.imports }}
+
+library {{ options.library_name }} {
+    enum Source {
+        NONE,
+        TransparentUpgradeableProxy,
+{{ sections.enum_variants }}
+    }
+
+    function deployableCount() internal pure returns (uint256) {
+        return {{ contracts | selectattr("deployable") | list | length }};
+    }
+{% for contract in contracts %}
+    // {{ contract.ident }}: {{ contract.kind }}
+{%- endfor %}
+}
+"#,
+    )
+    .unwrap();
+
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(generate_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let sources = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(sources.contains("// generated from src into scripts/reflections/Sources.s.sol"));
+    assert!(sources.contains("import { IToken as _IToken } from \"src/Token.sol\";"));
+    assert!(sources.contains("return 1;"));
+    assert!(sources.contains("    // IToken: interface\n    // Token: contract\n}"));
+    assert!(!sources.contains("toCreationCode"));
+
+    // a template using an unknown variable fails instead of generating an empty string
+    fs::write(dir.join(".reflections/templates/library.sol.j2"), "{{ options.library }}").unwrap();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(generate_cmd().into(), Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_generate_sharded() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src/tokens")).unwrap();
+    fs::create_dir_all(dir.join("src/vaults")).unwrap();
+    let header = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\n";
+    fs::write(dir.join("src/tokens/Token.sol"), format!("{header}contract Token {{}}")).unwrap();
+    fs::write(dir.join("src/vaults/Vault.sol"), format!("{header}contract Vault {{}}")).unwrap();
+
+    let cmd = |check: bool| {
+        Generate::builder()
+            .contracts_dir("src".to_string())
+            .output("scripts/reflections/Sources.s.sol".to_string())
+            .library_name("Sources".to_string())
+            .license("MIT".to_string())
+            .solidity_version("^0.8.0".to_string())
+            .shard_by("directory".parse().unwrap())
+            .check(check)
+            .build()
+    };
+    let root = dir.to_string_lossy().to_string();
+    let run_with = |cmd: Generate| {
+        async_with_vars(
+            [("REFLECTIONS_PROJECT_ROOT", Some(root.as_str()))],
+            run(cmd.into(), Verbosity::default()),
+        )
+    };
+
+    let res = run_with(cmd(false)).await;
+    assert!(res.is_ok(), "{res:?}");
+    let dispatcher = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(dispatcher.contains("import { SourcesTokens } from \"./SourcesTokens.s.sol\";"));
+    assert!(dispatcher.contains("Source internal constant Vault = Source.wrap((2 << 8) | 2);"));
+    let tokens = fs::read_to_string(dir.join("scripts/reflections/SourcesTokens.s.sol")).unwrap();
+    assert!(tokens.contains("library SourcesTokens {"));
+    assert!(dir.join("scripts/reflections/SourcesVaults.s.sol").exists());
+
+    // a new directory sorting first gets the next shard id instead of renumbering
+    fs::create_dir_all(dir.join("src/adapters")).unwrap();
+    fs::write(dir.join("src/adapters/Adapter.sol"), format!("{header}contract Adapter {{}}"))
+        .unwrap();
+    assert!(run_with(cmd(true)).await.is_err());
+    let res = run_with(cmd(false)).await;
+    assert!(res.is_ok(), "{res:?}");
+    let dispatcher = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(dispatcher.contains("Source internal constant Adapter = Source.wrap((3 << 8) | 2);"));
+    assert!(dispatcher.contains("Source internal constant Vault = Source.wrap((2 << 8) | 2);"));
+    let res = run_with(cmd(true)).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // a contract moving to another directory keeps the values of the others
+    fs::write(dir.join("src/tokens/Coin.sol"), format!("{header}contract Coin {{}}")).unwrap();
+    let res = run_with(cmd(false)).await;
+    assert!(res.is_ok(), "{res:?}");
+    let dispatcher = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(dispatcher.contains("Source internal constant Coin = Source.wrap((1 << 8) | 3);"));
+    fs::rename(dir.join("src/tokens/Token.sol"), dir.join("src/vaults/Token.sol")).unwrap();
+    let res = run_with(cmd(false)).await;
+    assert!(res.is_ok(), "{res:?}");
+    let dispatcher = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert!(dispatcher.contains("Source internal constant Coin = Source.wrap((1 << 8) | 3);"));
+    assert!(dispatcher.contains("Source internal constant Vault = Source.wrap((2 << 8) | 2);"));
+    assert!(dispatcher.contains("Source internal constant Token = Source.wrap((2 << 8) | 3);"));
+    let tokens = fs::read_to_string(dir.join("scripts/reflections/SourcesTokens.s.sol")).unwrap();
+    assert!(tokens.contains("        Removed_Token, // removed, keeps the later ordinals stable"));
+    let res = run_with(cmd(true)).await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // the dispatcher has no `Source` enum to recover the ordinals from without the lockfile
+    fs::remove_file(dir.join("reflections.lock")).unwrap();
+    let err = run_with(cmd(false)).await.unwrap_err();
+    assert!(err.to_string().contains("cannot be recovered"), "{err}");
+    assert!(!dir.join("reflections.lock").exists());
+    let after = fs::read_to_string(dir.join("scripts/reflections/Sources.s.sol")).unwrap();
+    assert_eq!(after, dispatcher);
+}
@@ -196,3 +196,23 @@ async fn test_init_updates_existing_config() {
     let config = fs::read_to_string(dir.join("reflections.toml")).unwrap();
     assert!(config.contains("v5.3.0"));
 }
+
+#[tokio::test]
+async fn test_init_foundry_script_dir() {
+    let dir = testdir!();
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("foundry.toml"), "[profile.default]\nscript = \"script\"\n").unwrap();
+
+    let cmd: Command = Init::builder().build().into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref())), ("FOUNDRY_PROFILE", None)],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    assert!(dir.join("script/reflections/di/Autowirable.s.sol").exists());
+    assert!(!dir.join("scripts").exists());
+    let gitignore = fs::read_to_string(dir.join(".gitignore")).unwrap();
+    assert!(gitignore.contains("/script/reflections/"));
+}
@@ -0,0 +1,147 @@
+use reflections_commands::{Command, Verbosity, commands::selectors::Selectors, run};
+use serde_json::json;
+use std::fs;
+use temp_env::async_with_vars;
+use testdir::testdir;
+
+fn function(name: &str, inputs: &[&str]) -> serde_json::Value {
+    json!({
+        "type": "function",
+        "name": name,
+        "inputs": inputs.iter().map(|ty| json!({ "name": "", "type": ty })).collect::<Vec<_>>(),
+        "outputs": [],
+        "stateMutability": "nonpayable"
+    })
+}
+
+fn erc20_abi() -> Vec<serde_json::Value> {
+    vec![
+        function("totalSupply", &[]),
+        function("balanceOf", &["address"]),
+        function("transfer", &["address", "uint256"]),
+        function("allowance", &["address", "address"]),
+        function("approve", &["address", "uint256"]),
+        function("transferFrom", &["address", "address", "uint256"]),
+        json!({
+            "type": "event",
+            "name": "Transfer",
+            "anonymous": false,
+            "inputs": [
+                { "name": "from", "type": "address", "indexed": true },
+                { "name": "to", "type": "address", "indexed": true },
+                { "name": "value", "type": "uint256", "indexed": false }
+            ]
+        }),
+    ]
+}
+
+#[tokio::test]
+async fn test_selectors() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/IERC20.sol"),
+        "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\ninterface IERC20 {}\n\ninterface IERC20Metadata is IERC20 {}\n",
+    )
+    .unwrap();
+
+    let mut metadata_abi = erc20_abi();
+    metadata_abi.extend(["name", "symbol", "decimals"].map(|name| function(name, &[])));
+    let mut token_abi = metadata_abi.clone();
+    token_abi.push(json!({
+        "type": "error",
+        "name": "ERC20InsufficientBalance",
+        "inputs": [
+            { "name": "sender", "type": "address" },
+            { "name": "balance", "type": "uint256" },
+            { "name": "needed", "type": "uint256" }
+        ]
+    }));
+    let collected = |name: &str, file: &str, abi: Vec<serde_json::Value>| json!({ "contract_name": name, "file_path": file, "abi": abi, "group": null });
+    let abis = json!({
+        "grouped": {
+            "Tokens": [collected("Token.0.8.24", "Token.sol/Token.0.8.24.json", token_abi.clone())]
+        },
+        "ungrouped": [
+            collected("IERC20", "IERC20.sol/IERC20.json", erc20_abi()),
+            collected("IERC20Metadata", "IERC20.sol/IERC20Metadata.json", metadata_abi),
+            collected("Token.0.8.20", "Token.sol/Token.0.8.20.json", token_abi)
+        ]
+    });
+    fs::write(dir.join("abis.json"), abis.to_string()).unwrap();
+
+    let cmd: Command = Selectors::builder()
+        .input("abis.json")
+        .output("script/Selectors.s.sol")
+        .library_name("Selectors")
+        .license("MIT")
+        .solidity_version("^0.8.0")
+        .build()
+        .into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    let library = fs::read_to_string(dir.join("script/Selectors.s.sol")).unwrap();
+    assert!(library.contains("library Selectors {\n    // IERC20\n"));
+    assert!(library.contains(
+        "    bytes4 internal constant IERC20_transfer = 0xa9059cbb; // transfer(address,uint256)"
+    ));
+    assert!(library.contains(
+        "    bytes32 internal constant IERC20_Transfer_TOPIC = 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef; // event Transfer(address,address,uint256)"
+    ));
+    assert!(library.contains(
+        "    bytes4 internal constant Token_ERC20InsufficientBalance_ERROR = 0xe450d38c; // error ERC20InsufficientBalance(address,uint256,uint256)"
+    ));
+    // ERC-165 ids leave out inherited functions
+    assert!(library.contains("    bytes4 internal constant IERC20_INTERFACE_ID = 0x36372b07;"));
+    assert!(
+        library.contains("    bytes4 internal constant IERC20Metadata_INTERFACE_ID = 0xa219a025;")
+    );
+    assert!(!library.contains("Token_INTERFACE_ID"));
+    // contracts compiled with several compilers are listed once
+    assert_eq!(library.matches("// Token\n").count(), 1);
+}
+
+#[tokio::test]
+async fn test_selectors_missing_input() {
+    let dir = testdir!();
+    let cmd: Command = Selectors::builder().input("abis.json").build().into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_selectors_unparsable_source() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/Broken.sol"), "pragma solidity ^0.8.0;\n\n/* unterminated").unwrap();
+    let abis = json!({
+        "grouped": {},
+        "ungrouped": [
+            { "contract_name": "IERC20", "file_path": "IERC20.sol/IERC20.json", "abi": erc20_abi(), "group": null }
+        ]
+    });
+    fs::write(dir.join("abis.json"), abis.to_string()).unwrap();
+
+    let cmd: Command =
+        Selectors::builder().input("abis.json").output("script/Selectors.s.sol").build().into();
+    let res = async_with_vars(
+        [("REFLECTIONS_PROJECT_ROOT", Some(dir.to_string_lossy().as_ref()))],
+        run(cmd, Verbosity::default()),
+    )
+    .await;
+    assert!(res.is_ok(), "{res:?}");
+
+    // the selectors are still generated, only the interface ids are skipped
+    let library = fs::read_to_string(dir.join("script/Selectors.s.sol")).unwrap();
+    assert!(library.contains("    bytes4 internal constant IERC20_transfer = 0xa9059cbb;"));
+    assert!(!library.contains("IERC20_INTERFACE_ID"));
+}
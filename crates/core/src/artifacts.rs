@@ -0,0 +1,340 @@
+//! Contract discovery from forge build artifacts
+//!
+//! Forge writes one artifact per compiled contract to `out/<File>.sol/<Contract>.json`. The
+//! artifact names the exact source path and contract, its AST gives the kind, parents, pragmas and
+//! NatSpec, and its bytecode tells whether the contract can be deployed. Projects that always build
+//! first can use artifacts instead of the source parser.
+
+use crate::{
+    create2::{decode_hex, keccak256},
+    errors::{GeneratorError, ReflectionsError},
+    imports::ImportResolver,
+    model::Dependency,
+    natspec::NatSpec,
+    parser::{BaseContract, ContractInfo, ContractKind, DiscoveryFilter, Parameter, resolve_bases},
+    pragma::VersionReq,
+};
+use log::debug;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// The contracts compiled into a build artifacts directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactIndex {
+    /// One entry per compiled contract, with its source path as import path, sorted by import
+    /// path and name.
+    pub contracts: Vec<ContractInfo>,
+    /// The creation code of the deployable contracts, as hex without `0x`, by source file and
+    /// contract name.
+    creation_code: BTreeMap<(PathBuf, String), String>,
+    /// The contracts compiled with several compiler versions into different creation code.
+    ambiguous: BTreeSet<(PathBuf, String)>,
+}
+
+impl ArtifactIndex {
+    /// Read the artifacts in `out_dir` (relative to `root`).
+    pub fn load(root: &Path, out_dir: &str) -> Result<Self, ReflectionsError> {
+        let out_path = root.join(out_dir);
+        if !out_path.is_dir() {
+            return Err(ReflectionsError::IOError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Artifacts directory not found: {out_dir}. Run 'forge build' first."),
+            )));
+        }
+
+        let mut contracts: Vec<ContractInfo> = Vec::new();
+        let mut creation_code: BTreeMap<_, String> = BTreeMap::new();
+        let mut ambiguous = BTreeSet::new();
+        for entry in WalkDir::new(&out_path)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        {
+            let path = entry.path();
+            // build-info files hold whole compilations, not contracts
+            if path.components().any(|c| c.as_os_str() == "build-info") {
+                continue;
+            }
+            let content = fs::read_to_string(path)?;
+            let Some((contract, artifact)) =
+                serde_json::from_str::<Value>(&content).ok().and_then(|artifact| {
+                    Some((contract_from_artifact(root, path, &artifact)?, artifact))
+                })
+            else {
+                debug!(path:? = path; "skipping file that is not a contract artifact");
+                continue;
+            };
+            let key = (contract.path.clone(), contract.name.clone());
+            if let Some(code) = bytecode_object(&artifact).filter(|code| !code.is_empty()) {
+                // the same contract compiled with several compiler versions has no single hash
+                if creation_code.get(&key).is_some_and(|existing| existing != code) {
+                    ambiguous.insert(key.clone());
+                }
+                creation_code.entry(key).or_insert_with(|| code.to_string());
+            }
+            if !contracts.iter().any(|c| c.name == contract.name && c.path == contract.path) {
+                contracts.push(contract);
+            }
+        }
+        contracts
+            .sort_by(|a, b| a.import_path.cmp(&b.import_path).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(Self { contracts, creation_code, ambiguous })
+    }
+
+    /// The creation code of a compiled contract as hex without `0x`, `None` if it has none.
+    ///
+    /// Libraries the contract links against are left as `__$<hash>$__` placeholders. Fails if the
+    /// contract was compiled with several compiler versions into different creation code.
+    pub fn creation_code(&self, contract: &ContractInfo) -> Result<Option<&str>, GeneratorError> {
+        let key = (contract.path.clone(), contract.name.clone());
+        if self.ambiguous.contains(&key) {
+            return Err(GeneratorError::AmbiguousArtifact(contract.name.clone()));
+        }
+        Ok(self.creation_code.get(&key).map(String::as_str))
+    }
+
+    /// The Keccak-256 hash of the creation code of a compiled contract, `None` if it has none or
+    /// links against libraries, which makes it depend on where they are deployed.
+    pub fn init_code_hash(
+        &self,
+        contract: &ContractInfo,
+    ) -> Result<Option<[u8; 32]>, GeneratorError> {
+        Ok(self
+            .creation_code(contract)?
+            .filter(|code| !code.contains("__$"))
+            .and_then(decode_hex)
+            .map(|code| keccak256(&code)))
+    }
+
+    /// The contracts declared in the files of `src_dir` accepted by `filter`, together with the
+    /// requested `dependencies`, with their parents resolved.
+    ///
+    /// Dependencies are imported through the remapping targeting them, like
+    /// [`crate::model::ProjectModel::add_dependencies`] does.
+    pub fn contracts(
+        &self,
+        src_dir: &str,
+        filter: &DiscoveryFilter,
+        dependencies: &[Dependency],
+        resolver: &ImportResolver,
+    ) -> Result<Vec<ContractInfo>, ReflectionsError> {
+        let matcher = filter.matcher()?;
+        let prefix = format!("{}/", src_dir.trim_end_matches('/'));
+        let mut contracts: Vec<_> = self
+            .contracts
+            .iter()
+            .filter(|c| {
+                c.import_path
+                    .strip_prefix(&prefix)
+                    .is_some_and(|relative| matcher.is_match(relative))
+            })
+            .cloned()
+            .collect();
+
+        for dependency in dependencies {
+            let resolved = resolver
+                .resolve("", &dependency.path)
+                .ok_or_else(|| GeneratorError::UnresolvedDependency(dependency.path.clone()))?;
+            let compiled: Vec<_> = self
+                .contracts
+                .iter()
+                .filter(|c| c.import_path == resolved)
+                .filter(|c| dependency.contract.as_ref().is_none_or(|name| &c.name == name))
+                .collect();
+            if compiled.is_empty() {
+                return Err(GeneratorError::MissingArtifact(dependency.to_string()).into());
+            }
+            for contract in compiled {
+                if !contracts.iter().any(|c| c.name == contract.name && c.path == contract.path) {
+                    let import_path = resolver.import_path(&resolved);
+                    contracts.push(ContractInfo { import_path, ..contract.clone() });
+                }
+            }
+        }
+
+        resolve_bases(&mut contracts);
+        contracts
+            .sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.import_path.cmp(&b.import_path)));
+        Ok(contracts)
+    }
+}
+
+/// Read the contract a forge artifact was compiled from, `None` if the file is no artifact.
+fn contract_from_artifact(root: &Path, path: &Path, artifact: &Value) -> Option<ContractInfo> {
+    let abi = artifact.get("abi")?.as_array()?;
+
+    // `metadata` is an object in recent forge versions and a string in older ones
+    let metadata = match artifact.get("metadata").or_else(|| artifact.get("rawMetadata")) {
+        Some(Value::String(raw)) => serde_json::from_str(raw).ok(),
+        metadata => metadata.cloned(),
+    };
+    let target = metadata
+        .as_ref()
+        .and_then(|m| m.pointer("/settings/compilationTarget"))
+        .and_then(Value::as_object)
+        .and_then(|target| target.iter().next())
+        .and_then(|(source, name)| Some((source.clone(), name.as_str()?.to_string())));
+    let ast = artifact.get("ast");
+
+    let (source, name) = match target {
+        Some(target) => target,
+        None => {
+            // `<Contract>.json`, or `<Contract>.<version>.json` when compiled more than once
+            let stem = path.file_stem()?.to_str()?;
+            let name = stem.split_once('.').map_or(stem, |(name, _)| name).to_string();
+            (ast?.get("absolutePath")?.as_str()?.to_string(), name)
+        }
+    };
+
+    let definition = ast.and_then(|ast| {
+        ast.get("nodes")?.as_array()?.iter().find(|node| {
+            node.get("nodeType").and_then(Value::as_str) == Some("ContractDefinition") &&
+                node.get("name").and_then(Value::as_str) == Some(&name)
+        })
+    });
+
+    let contract_kind = definition.and_then(|d| d.get("contractKind")).and_then(Value::as_str);
+    let is_abstract = definition.and_then(|d| d.get("abstract")).and_then(Value::as_bool);
+    let kind = match (contract_kind, is_abstract) {
+        (Some("interface"), _) => ContractKind::Interface,
+        (Some("library"), _) => ContractKind::Library,
+        (Some(_), Some(true)) => ContractKind::AbstractContract,
+        (Some(_), _) => ContractKind::Contract,
+        // without an AST, only the bytecode tells deployable contracts from the others
+        (None, _) if has_bytecode(artifact) => ContractKind::Contract,
+        (None, _) => ContractKind::AbstractContract,
+    };
+
+    let bases = definition
+        .and_then(|d| d.get("baseContracts")?.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|base| {
+            let name =
+                base.pointer("/baseName/name").or_else(|| base.pointer("/baseName/namePath"));
+            Some(BaseContract {
+                name: name?.as_str()?.to_string(),
+                arguments: None,
+                resolved_path: None,
+            })
+        })
+        .collect();
+
+    let constructor_params = abi
+        .iter()
+        .find(|item| item.get("type").and_then(Value::as_str) == Some("constructor"))
+        .and_then(|constructor| constructor.get("inputs")?.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(abi_parameter)
+        .collect();
+
+    let natspec = match definition.and_then(|d| d.get("documentation")) {
+        Some(Value::String(text)) => NatSpec::parse([text.as_str()]),
+        Some(documentation) => documentation
+            .get("text")
+            .and_then(Value::as_str)
+            .map(|text| NatSpec::parse([text]))
+            .unwrap_or_default(),
+        None => NatSpec::default(),
+    };
+
+    Some(ContractInfo {
+        name,
+        path: root.join(&source),
+        import_path: source,
+        kind,
+        bases,
+        constructor_params,
+        pragma: ast.and_then(pragma_from_ast),
+        natspec,
+    })
+}
+
+/// Whether the artifact has creation code.
+fn has_bytecode(artifact: &Value) -> bool {
+    bytecode_object(artifact).is_some_and(|object| !object.is_empty())
+}
+
+/// The creation code of an artifact as hex without `0x`.
+fn bytecode_object(artifact: &Value) -> Option<&str> {
+    let bytecode = artifact.get("bytecode");
+    let object = bytecode.and_then(|b| b.get("object")).or(bytecode).and_then(Value::as_str)?;
+    Some(object.trim_start_matches("0x"))
+}
+
+/// A constructor parameter from its ABI description, typed as in the source when possible.
+fn abi_parameter(input: &Value) -> Option<Parameter> {
+    // `internalType` keeps user-defined types, e.g. `struct Vault.Config` or `contract IERC20`
+    let internal = input.get("internalType").and_then(Value::as_str);
+    let type_name = match internal {
+        Some(internal) => ["struct ", "contract ", "enum "]
+            .iter()
+            .find_map(|prefix| internal.strip_prefix(prefix))
+            .unwrap_or(internal),
+        None => input.get("type")?.as_str()?,
+    };
+    let name = input.get("name").and_then(Value::as_str).filter(|name| !name.is_empty());
+    Some(Parameter {
+        type_name: type_name.to_string(),
+        location: None,
+        name: name.map(String::from),
+        indexed: false,
+    })
+}
+
+/// The combined `pragma solidity` requirement of a source unit AST.
+fn pragma_from_ast(ast: &Value) -> Option<VersionReq> {
+    let requirements: Vec<VersionReq> = ast
+        .get("nodes")?
+        .as_array()?
+        .iter()
+        .filter(|node| node.get("nodeType").and_then(Value::as_str) == Some("PragmaDirective"))
+        .filter_map(|node| {
+            let literals: Vec<_> =
+                node.get("literals")?.as_array()?.iter().filter_map(Value::as_str).collect();
+            let (first, rest) = literals.split_first()?;
+            (*first == "solidity").then(|| join_pragma_literals(rest).parse().ok()).flatten()
+        })
+        .collect();
+    requirements.into_iter().reduce(|a, b| a.intersect(&b))
+}
+
+/// Join the literals of a pragma AST node, e.g. `[">=", "0.8", ".0", "<", "0.9", ".0"]` into
+/// `>=0.8.0 <0.9.0`.
+fn join_pragma_literals(literals: &[&str]) -> String {
+    let mut pragma = String::new();
+    let mut previous = "";
+    for literal in literals {
+        let starts_comparator = literal.starts_with(['^', '~', '<', '>', '=', '|', '-']);
+        if !pragma.is_empty() && (starts_comparator || previous == "-" || previous == "||") {
+            pragma.push(' ');
+        }
+        pragma.push_str(literal);
+        previous = literal;
+    }
+    pragma
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_pragma_literals() {
+        assert_eq!(join_pragma_literals(&["^", "0.8", ".20"]), "^0.8.20");
+        assert_eq!(join_pragma_literals(&[">=", "0.8", ".0", "<", "0.9", ".0"]), ">=0.8.0 <0.9.0");
+        assert_eq!(join_pragma_literals(&["0.8", ".0", "-", "0.8", ".19"]), "0.8.0 - 0.8.19");
+        assert_eq!(
+            join_pragma_literals(&["0.6", ".12", "||", "^", "0.8", ".0"]),
+            "0.6.12 || ^0.8.0"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Content-hash cache for contract discovery
+//!
+//! Parsed source units are stored per file together with the SHA-256 of the file contents, so a
+//! file is only parsed again once it changes. The cache is tied to the version of Reflections that
+//! wrote it and silently starts over when it is missing, unreadable or outdated.
+
+use crate::{Result, parser::SourceUnit};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The location of the discovery cache, relative to the project root.
+pub const DISCOVERY_CACHE_PATH: &str = ".reflections/cache/discovery.json";
+
+/// Bumped whenever the parser records something new, so older caches are not reused.
+const CACHE_FORMAT: u32 = 4;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: String,
+    format: u32,
+    files: BTreeMap<String, CachedUnit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedUnit {
+    hash: String,
+    unit: SourceUnit,
+}
+
+/// The parsed source units of a project, keyed by their path relative to the project root.
+#[derive(Debug)]
+pub struct DiscoveryCache {
+    root: PathBuf,
+    files: BTreeMap<String, CachedUnit>,
+}
+
+impl DiscoveryCache {
+    /// Load the cache of the project at `root`, or start an empty one.
+    pub fn load(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref().to_path_buf();
+        let path = root.join(DISCOVERY_CACHE_PATH);
+
+        let files = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<CacheFile>(&content).ok())
+            .filter(|cache| {
+                cache.version == env!("CARGO_PKG_VERSION") && cache.format == CACHE_FORMAT
+            })
+            .map(|cache| cache.files)
+            .unwrap_or_else(|| {
+                debug!(path:? = path; "no usable discovery cache, starting a new one");
+                BTreeMap::new()
+            });
+
+        Self { root, files }
+    }
+
+    /// Write the cache, dropping the entries of files that no longer exist.
+    pub fn save(&mut self) -> Result<()> {
+        let root = &self.root;
+        self.files.retain(|key, _| root.join(key).exists());
+
+        let path = self.root.join(DISCOVERY_CACHE_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let cache = CacheFile {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            format: CACHE_FORMAT,
+            files: std::mem::take(&mut self.files),
+        };
+        let content = serde_json::to_string(&cache).map_err(io::Error::from);
+        self.files = cache.files;
+        fs::write(path, content?)?;
+        Ok(())
+    }
+
+    /// Get the cached source unit of a file if its contents are unchanged.
+    pub(crate) fn get(&self, key: &str, hash: &str) -> Option<&SourceUnit> {
+        self.files.get(key).filter(|cached| cached.hash == hash).map(|cached| &cached.unit)
+    }
+
+    pub(crate) fn insert(&mut self, key: String, hash: String, unit: SourceUnit) {
+        self.files.insert(key, CachedUnit { hash, unit });
+    }
+
+    /// The hash the cache compares file contents by.
+    pub(crate) fn hash(content: &str) -> String {
+        Sha256::digest(content.as_bytes()).iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
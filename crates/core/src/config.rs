@@ -1,8 +1,9 @@
 //! Manage the Reflections configuration
-use crate::errors::ConfigError;
+use crate::{create2::SaltNamespace, errors::ConfigError};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     env,
     path::{Path, PathBuf},
 };
@@ -23,6 +24,24 @@ pub struct Paths {
 
     /// The path to the remappings file (does not need to exist).
     pub remappings: PathBuf,
+
+    /// The contracts directory (`src` in foundry.toml).
+    pub src: PathBuf,
+
+    /// The build artifacts directory (`out` in foundry.toml).
+    pub out: PathBuf,
+
+    /// The dependency directories (`libs` in foundry.toml).
+    pub libs: Vec<PathBuf>,
+
+    /// The scripts directory (`script` in foundry.toml), where the DI framework is scaffolded.
+    pub script: PathBuf,
+
+    /// The tests directory (`test` in foundry.toml).
+    pub test: PathBuf,
+
+    /// The remappings declared in foundry.toml, as `prefix=target` entries.
+    pub config_remappings: Vec<String>,
 }
 
 impl Paths {
@@ -45,8 +64,33 @@ impl Paths {
         let root = root.as_ref();
         let config = Self::get_config_path(root, config_location)?;
         let remappings = root.join("remappings.txt");
+        let profile = FoundryProfile::load(root.join("foundry.toml"), &Self::get_profile_name())?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            config,
+            remappings,
+            src: root.join(profile.src),
+            out: root.join(profile.out),
+            libs: profile.libs.iter().map(|lib| root.join(lib)).collect(),
+            script: root.join(profile.script),
+            test: root.join(profile.test),
+            config_remappings: profile.remappings,
+        })
+    }
 
-        Ok(Self { root: root.to_path_buf(), config, remappings })
+    /// Express a path relative to the project root, with forward slashes (e.g. `src`).
+    pub fn relative_to_root(&self, path: impl AsRef<Path>) -> String {
+        let path = path.as_ref();
+        path.strip_prefix(&self.root).unwrap_or(path).display().to_string().replace('\\', "/")
+    }
+
+    /// Get the name of the active Foundry profile.
+    pub fn get_profile_name() -> String {
+        env::var("FOUNDRY_PROFILE")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| "default".to_string())
     }
 
     /// Get the root directory path.
@@ -119,6 +163,101 @@ pub fn detect_config_location(root: impl AsRef<Path>) -> Option<ConfigLocation>
     }
 }
 
+/// The project layout of the active profile in foundry.toml.
+///
+/// Keys missing from the active profile are taken from `[profile.default]`, then from Foundry's
+/// defaults. The only exception is `script`: Reflections has always scaffolded into `scripts/`,
+/// so that stays the default unless foundry.toml sets it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundryProfile {
+    pub src: String,
+    pub out: String,
+    pub libs: Vec<String>,
+    pub script: String,
+    pub test: String,
+    pub remappings: Vec<String>,
+}
+
+impl Default for FoundryProfile {
+    fn default() -> Self {
+        Self {
+            src: "src".to_string(),
+            out: "out".to_string(),
+            libs: vec!["lib".to_string()],
+            script: "scripts".to_string(),
+            test: "test".to_string(),
+            remappings: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FoundryToml {
+    #[serde(default)]
+    profile: HashMap<String, RawFoundryProfile>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+struct RawFoundryProfile {
+    src: Option<String>,
+    out: Option<String>,
+    libs: Option<Vec<String>>,
+    script: Option<String>,
+    test: Option<String>,
+    remappings: Option<Vec<String>>,
+}
+
+impl RawFoundryProfile {
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            src: self.src.or(fallback.src),
+            out: self.out.or(fallback.out),
+            libs: self.libs.or(fallback.libs),
+            script: self.script.or(fallback.script),
+            test: self.test.or(fallback.test),
+            remappings: self.remappings.or(fallback.remappings),
+        }
+    }
+}
+
+impl FoundryProfile {
+    /// Load a profile from foundry.toml, or the defaults if the file doesn't exist
+    pub fn load(config_path: impl AsRef<Path>, profile: &str) -> Result<Self> {
+        let config_path = config_path.as_ref();
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(config_path)?;
+        Self::parse(&content, profile)
+    }
+
+    /// Parse a profile from the contents of foundry.toml
+    pub fn parse(content: &str, profile: &str) -> Result<Self> {
+        let mut config: FoundryToml = toml_edit::de::from_str(content)?;
+        let base = config.profile.remove("default").unwrap_or_default();
+        let raw = match config.profile.remove(profile) {
+            Some(selected) => selected.or(base),
+            None => {
+                if profile != "default" {
+                    debug!(profile; "profile not found in foundry.toml, using default");
+                }
+                base
+            }
+        };
+
+        let defaults = Self::default();
+        Ok(Self {
+            src: raw.src.unwrap_or(defaults.src),
+            out: raw.out.unwrap_or(defaults.out),
+            libs: raw.libs.unwrap_or(defaults.libs),
+            script: raw.script.unwrap_or(defaults.script),
+            test: raw.test.unwrap_or(defaults.test),
+            remappings: raw.remappings.unwrap_or(defaults.remappings),
+        })
+    }
+}
+
 /// Reflections configuration structure
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -130,6 +269,61 @@ pub struct ReflectionsConfig {
     /// zkSync-OS repository URL
     #[serde(default = "default_zksync_os_url")]
     pub zksync_os_url: String,
+
+    /// Settings for the `generate` command
+    #[serde(default, skip_serializing_if = "GenerateConfig::is_default")]
+    pub generate: GenerateConfig,
+}
+
+/// The `[generate]` section of reflections.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GenerateConfig {
+    /// Glob patterns of contract files to include (relative to the contracts directory)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Glob patterns of contract files to exclude (relative to the contracts directory)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Skip Foundry test files (`*.t.sol`)
+    #[serde(default)]
+    pub skip_tests: bool,
+
+    /// Skip Foundry script files (`*.s.sol`)
+    #[serde(default)]
+    pub skip_scripts: bool,
+
+    /// Contracts from outside the contracts directory, as `<path>` or `<path>:<contract>`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+
+    /// Read the contracts from the forge build artifacts instead of parsing the sources
+    #[serde(default)]
+    pub from_artifacts: bool,
+
+    /// Generate `initCodeHash` and `predictAddress` from the creation code in the build artifacts
+    #[serde(default)]
+    pub init_code_hashes: bool,
+
+    /// Generate a Foundry test deploying every deployable source through the wiring
+    #[serde(default)]
+    pub smoke_tests: bool,
+
+    /// Directory with templates overriding the built-in ones (relative to the project root)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template_dir: Option<String>,
+
+    /// The namespace mixed into the salts of `toSalt`
+    #[serde(default, skip_serializing_if = "SaltNamespace::is_empty")]
+    pub salt_namespace: SaltNamespace,
+}
+
+impl GenerateConfig {
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
 }
 
 fn default_openzeppelin_version() -> String {
@@ -145,6 +339,7 @@ impl Default for ReflectionsConfig {
         Self {
             openzeppelin_version: default_openzeppelin_version(),
             zksync_os_url: default_zksync_os_url(),
+            generate: GenerateConfig::default(),
         }
     }
 }
@@ -173,3 +368,43 @@ impl ReflectionsConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_foundry_profile_defaults() {
+        let profile = FoundryProfile::parse("[fmt]\nline_length = 100\n", "default").unwrap();
+        assert_eq!(profile, FoundryProfile::default());
+    }
+
+    #[test]
+    fn test_foundry_profile_selection() {
+        let content = r#"
+[profile.default]
+src = "contracts"
+out = "build"
+libs = ["node_modules", "lib"]
+remappings = ["@openzeppelin/=lib/openzeppelin-contracts/"]
+
+[profile.ci]
+out = "ci-build"
+"#;
+        let profile = FoundryProfile::parse(content, "default").unwrap();
+        assert_eq!(profile.src, "contracts");
+        assert_eq!(profile.out, "build");
+        assert_eq!(profile.libs, ["node_modules", "lib"]);
+        assert_eq!(profile.script, "scripts");
+        assert_eq!(profile.test, "test");
+        assert_eq!(profile.remappings, ["@openzeppelin/=lib/openzeppelin-contracts/"]);
+
+        // other profiles inherit what they don't set from the default profile
+        let profile = FoundryProfile::parse(content, "ci").unwrap();
+        assert_eq!(profile.src, "contracts");
+        assert_eq!(profile.out, "ci-build");
+
+        let profile = FoundryProfile::parse(content, "missing").unwrap();
+        assert_eq!(profile.out, "build");
+    }
+}
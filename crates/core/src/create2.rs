@@ -0,0 +1,263 @@
+//! CREATE2 address prediction
+//!
+//! The generated library derives the salt of a source from its name, `toSalt(Source)` being
+//! `keccak256(abi.encodePacked(name))` and `toSalt(Source, ShortString)`
+//! `keccak256(abi.encodePacked(name, nickname))`. A [`SaltNamespace`] prefixes the name with
+//! `SALT_NAMESPACE`, `keccak256(abi.encode(project, version))`, and `block.chainid`. Together with
+//! the creation code of the source and the deploying address, the salt determines the address
+//! `Create2.deploy` deploys to.
+
+use crate::errors::GeneratorError;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use tiny_keccak::{Hasher, Keccak};
+
+/// The longest nickname a `ShortString` can hold.
+pub const MAX_NICKNAME_LEN: usize = 31;
+
+/// A 20-byte account address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address(pub [u8; 20]);
+
+impl FromStr for Address {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s
+            .strip_prefix("0x")
+            .and_then(decode_hex)
+            .and_then(|bytes| <[u8; 20]>::try_from(bytes).ok())
+            .ok_or_else(|| GeneratorError::InvalidAddress(s.to_string()))?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Formats the address with its EIP-55 checksum.
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lower = encode_hex(&self.0);
+        let hash = keccak256(lower.as_bytes());
+        let checksummed: String = lower
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+                if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+            })
+            .collect();
+        write!(f, "0x{checksummed}")
+    }
+}
+
+/// The Keccak-256 hash of `data`.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+/// The namespace mixed into every salt, so that projects deploying through the same CREATE2
+/// factory, and versions of the same project, get different addresses for equally named sources.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SaltNamespace {
+    /// The project id, e.g. `acme-vaults`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub project: String,
+
+    /// The version of the deployment, e.g. `v2`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+
+    /// Also mix in `block.chainid`, giving the sources different addresses on every chain.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub chain_id: bool,
+}
+
+impl SaltNamespace {
+    /// Whether the salts are those of a library without namespace.
+    pub fn is_empty(&self) -> bool {
+        self.id().is_none() && !self.chain_id
+    }
+
+    /// `<project>@<version>`, naming the namespace. `None` if both are empty.
+    pub fn id(&self) -> Option<String> {
+        if self.project.is_empty() && self.version.is_empty() {
+            return None;
+        }
+        Some(format!("{}@{}", self.project, self.version))
+    }
+
+    /// `SALT_NAMESPACE`, `keccak256(abi.encode(project, version))`. Unlike the id, the encoding
+    /// tells `a@b` + `c` from `a` + `b@c`. `None` if both are empty.
+    pub fn hash(&self) -> Option<[u8; 32]> {
+        self.id()?;
+        let (project, version) = (self.project.as_bytes(), self.version.as_bytes());
+        let padded = |len: usize| len.div_ceil(32) * 32;
+        // the offsets of both strings, then each length and padded contents
+        let mut encoded = Vec::new();
+        encoded.extend_from_slice(&abi_word(64));
+        encoded.extend_from_slice(&abi_word(64 + 32 + padded(project.len())));
+        for value in [project, version] {
+            encoded.extend_from_slice(&abi_word(value.len()));
+            encoded.extend_from_slice(value);
+            encoded.resize(encoded.len() + padded(value.len()) - value.len(), 0);
+        }
+        Some(keccak256(&encoded))
+    }
+
+    /// Check that the id can be written as a Solidity string literal.
+    pub fn validate(&self) -> Result<(), GeneratorError> {
+        match self.id() {
+            Some(id) if !id.chars().all(|c| c.is_ascii_graphic() && c != '"' && c != '\\') => {
+                Err(GeneratorError::InvalidSaltNamespace(id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The salt `toSalt` derives for a source name, and optionally a nickname, on `chain_id`.
+    /// The chain id is only needed if the namespace includes it.
+    pub fn salt(
+        &self,
+        chain_id: Option<u64>,
+        name: &str,
+        nickname: Option<&str>,
+    ) -> Result<[u8; 32], GeneratorError> {
+        self.validate()?;
+        let mut packed = Vec::new();
+        if let Some(hash) = self.hash() {
+            packed.extend_from_slice(&hash);
+        }
+        if self.chain_id {
+            let chain_id = chain_id.ok_or(GeneratorError::MissingChainId)?;
+            // `block.chainid` is a uint256
+            packed.extend_from_slice(&[0; 24]);
+            packed.extend_from_slice(&chain_id.to_be_bytes());
+        }
+        packed.extend_from_slice(name.as_bytes());
+        match nickname {
+            Some(nickname) if nickname.len() > MAX_NICKNAME_LEN => {
+                return Err(GeneratorError::InvalidNickname(nickname.to_string()));
+            }
+            Some(nickname) => packed.extend_from_slice(nickname.as_bytes()),
+            None => {}
+        }
+        Ok(keccak256(&packed))
+    }
+}
+
+/// `value` as a big-endian 32-byte ABI word.
+fn abi_word(value: usize) -> [u8; 32] {
+    let mut word = [0; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// The address `deployer` deploys `init_code_hash` to with `salt`, as specified in EIP-1014.
+pub fn create2_address(deployer: &Address, salt: &[u8; 32], init_code_hash: &[u8; 32]) -> Address {
+    let mut input = Vec::with_capacity(85);
+    input.push(0xff);
+    input.extend_from_slice(&deployer.0);
+    input.extend_from_slice(salt);
+    input.extend_from_slice(init_code_hash);
+    let hash = keccak256(&input);
+
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    Address(address)
+}
+
+/// Decode a hex string without `0x` prefix, `None` if it is not valid hex.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Encode bytes as lowercase hex without `0x` prefix.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create2_address() {
+        // the examples of EIP-1014
+        let address = create2_address(&Address::default(), &[0; 32], &keccak256(&[0]));
+        assert_eq!(address.to_string(), "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38");
+
+        let deployer: Address = "0xdeadbeef00000000000000000000000000000000".parse().unwrap();
+        let salt: [u8; 32] =
+            decode_hex("000000000000000000000000feed000000000000000000000000000000000000")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let address = create2_address(&deployer, &salt, &keccak256(&[0]));
+        assert_eq!(address.to_string(), "0xD04116cDd17beBE565EB2422F2497E06cC1C9833");
+    }
+
+    #[test]
+    fn test_salt() {
+        let none = SaltNamespace::default();
+        assert_eq!(none.salt(None, "Vault", None).unwrap(), keccak256(b"Vault"));
+        assert_eq!(none.salt(None, "Vault", Some("alice")).unwrap(), keccak256(b"Vaultalice"));
+        assert!(matches!(
+            none.salt(None, "Vault", Some(&"a".repeat(32))),
+            Err(GeneratorError::InvalidNickname(_))
+        ));
+
+        let namespace =
+            SaltNamespace { project: "acme".into(), version: "v2".into(), chain_id: false };
+        // abi.encode("acme", "v2")
+        let encoded = decode_hex(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000080",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "61636d6500000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "7632000000000000000000000000000000000000000000000000000000000000",
+        ))
+        .unwrap();
+        let hash = keccak256(&encoded);
+        assert_eq!(namespace.hash(), Some(hash));
+        let mut packed = hash.to_vec();
+        packed.extend_from_slice(b"Vaultalice");
+        assert_eq!(namespace.salt(None, "Vault", Some("alice")).unwrap(), keccak256(&packed));
+
+        // ids that read the same are different namespaces
+        let split = |project: &str, version: &str| SaltNamespace {
+            project: project.into(),
+            version: version.into(),
+            chain_id: false,
+        };
+        assert_eq!(split("a@b", "c").id(), split("a", "b@c").id());
+        assert_ne!(split("a@b", "c").hash(), split("a", "b@c").hash());
+        assert_eq!(SaltNamespace::default().hash(), None);
+
+        let namespace = SaltNamespace { chain_id: true, ..namespace };
+        assert!(matches!(namespace.salt(None, "Vault", None), Err(GeneratorError::MissingChainId)));
+        let mut packed = hash.to_vec();
+        packed.extend_from_slice(&[0; 31]);
+        packed.push(10);
+        packed.extend_from_slice(b"Vault");
+        assert_eq!(namespace.salt(Some(10), "Vault", None).unwrap(), keccak256(&packed));
+
+        let namespace = SaltNamespace { project: "a\"b".into(), ..Default::default() };
+        assert!(matches!(namespace.validate(), Err(GeneratorError::InvalidSaltNamespace(_))));
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let address: Address = "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38".parse().unwrap();
+        assert_eq!(address.to_string(), "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38");
+        assert!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38".parse::<Address>().is_err());
+        assert!("0x4D1A".parse::<Address>().is_err());
+    }
+}
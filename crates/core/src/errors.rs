@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, path::PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,6 +9,15 @@ pub enum ReflectionsError {
 
     #[error("error during IO operation: {0}")]
     IOError(#[from] io::Error),
+
+    #[error("error during generation: {0}")]
+    GeneratorError(#[from] GeneratorError),
+
+    #[error("invalid glob pattern: {0}")]
+    GlobError(#[from] globset::Error),
+
+    #[error("error parsing {}: {source}", path.display())]
+    ParseError { path: PathBuf, source: ParseError },
 }
 
 #[derive(Error, Debug)]
@@ -29,3 +38,116 @@ pub enum ConfigError {
     #[error("invalid config location: {0}")]
     InvalidLocation(String),
 }
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum GeneratorError {
+    #[error("invalid enum policy: {0}")]
+    InvalidEnumPolicy(String),
+
+    #[error("contract `{name}` is declared more than once: {}", paths.join(", "))]
+    DuplicateContract { name: String, paths: Vec<String> },
+
+    #[error("invalid solidity version requirement: {0}")]
+    InvalidSolidityVersion(String),
+
+    #[error(
+        "pragma `{requested}` cannot be satisfied together with the imported files, which require `{required}`"
+    )]
+    IncompatiblePragma { requested: String, required: String },
+
+    #[error("invalid dependency `{0}`, expected `<path>` or `<path>:<contract>`")]
+    InvalidDependency(String),
+
+    #[error("dependency `{0}` does not resolve to a file, check the remappings")]
+    UnresolvedDependency(String),
+
+    #[error("contract `{contract}` is not declared in {path}")]
+    UnknownDependencyContract { contract: String, path: String },
+
+    #[error("dependency `{0}` has no build artifact, run `forge build` first")]
+    MissingArtifact(String),
+
+    #[error(
+        "`{0}` was compiled with several compiler versions into different creation code, build it with a single one"
+    )]
+    AmbiguousArtifact(String),
+
+    #[error("invalid reflections.lock: {0}")]
+    InvalidLock(String),
+
+    #[error("{0} is out of date, run `reflections generate` to update it")]
+    OutdatedOutput(String),
+
+    #[error(
+        "reflections.lock is missing and the ordinals of the sharded sources in {0} cannot be recovered from it; restore reflections.lock, or delete the libraries to renumber the sources"
+    )]
+    UnrecoverableOrdinals(String),
+
+    #[error("error rendering template {0}")]
+    Template(String),
+
+    #[error("invalid sharding: {0}, expected `none`, `directory` or `count`")]
+    InvalidSharding(String),
+
+    #[error("invalid shard size {0}, expected 1 to 254 variants")]
+    InvalidShardSize(usize),
+
+    #[error(
+        "{library} would have {count} `Source` variants, more than the 254 an enum can hold; shard it with `--shard-by`"
+    )]
+    TooManyVariants { library: String, count: usize },
+
+    #[error("invalid address `{0}`, expected 0x followed by 40 hex digits")]
+    InvalidAddress(String),
+
+    #[error("nickname `{0}` is longer than the 31 bytes of a ShortString")]
+    InvalidNickname(String),
+
+    #[error(
+        "invalid salt namespace `{0}`: only ASCII letters, digits and punctuation other than quotes and backslashes are allowed"
+    )]
+    InvalidSaltNamespace(String),
+
+    #[error("the salt namespace includes the chain id, pass the chain to predict addresses for")]
+    MissingChainId,
+
+    #[error("no compiler version satisfies the generated library ({}) and all imported files: {}", crate::generator::LIBRARY_PRAGMA, paths.join(", "))]
+    NoCompatiblePragma { paths: Vec<String> },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    #[error("unterminated comment starting on line {line}")]
+    UnterminatedComment { line: usize },
+
+    #[error("unterminated string literal on line {line}")]
+    UnterminatedString { line: usize },
+
+    #[error("invalid `pragma solidity {pragma}` on line {line}")]
+    InvalidPragma { pragma: String, line: usize },
+
+    #[error("expected {expected} on line {line}, found `{found}`")]
+    UnexpectedToken { expected: &'static str, found: String, line: usize },
+
+    #[error("unexpected end of file, expected {expected}")]
+    UnexpectedEof { expected: &'static str },
+
+    #[error("unbalanced `{delimiter}` on line {line}")]
+    UnbalancedDelimiter { delimiter: char, line: usize },
+}
+
+/// A diagnostic about an `import` directive found while resolving the import graph.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImportError {
+    #[error("{file}:{line}: import \"{import}\" does not resolve to a file")]
+    Unresolved { file: String, import: String, line: usize },
+
+    #[error("could not read the imports of {file}: {reason}")]
+    Unreadable { file: String, reason: String },
+
+    #[error("could not parse the imports of {file}: {source}")]
+    Unparsable { file: String, source: ParseError },
+}
@@ -1,11 +1,125 @@
 //! Generator for Solidity reflection library
 
-use crate::parser::ContractInfo;
+use crate::{
+    create2::{SaltNamespace, encode_hex},
+    errors::GeneratorError,
+    imports::ImportGraph,
+    lockfile::{FIRST_ORDINAL, LockedVariant, SourceLock},
+    parser::{ContractInfo, ContractKind, Parameter},
+    pragma::VersionReq,
+    template::{
+        LibraryTemplate, TemplateContext, TemplateContract, TemplateOptions, TemplatePaths,
+        TemplateSections, TemplateShard,
+    },
+};
+use similar::TextDiff;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Which discovered contracts become variants of the generated `Source` enum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EnumPolicy {
+    /// Every discovered contract, abstract contract, interface and library gets a variant.
+    #[default]
+    All,
+    /// Only contracts and libraries, which have creation code, get a variant.
+    Deployable,
+}
+
+impl EnumPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Deployable => "deployable",
+        }
+    }
+
+    /// Whether declarations of this kind get a variant.
+    pub fn includes(&self, kind: ContractKind) -> bool {
+        *self == Self::All || kind.is_deployable()
+    }
+}
+
+impl std::str::FromStr for EnumPolicy {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "deployable" => Ok(Self::Deployable),
+            _ => Err(GeneratorError::InvalidEnumPolicy(s.to_string())),
+        }
+    }
+}
+
+/// How the `Source` enum is split over several libraries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Sharding {
+    /// A single library with a single `Source` enum.
+    #[default]
+    None,
+    /// One library per top-level directory of the contracts directory (and per dependency).
+    Directory,
+    /// Libraries of at most `shard_size` variants, in ordinal order.
+    Count,
+}
+
+impl Sharding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Directory => "directory",
+            Self::Count => "count",
+        }
+    }
+}
+
+impl std::str::FromStr for Sharding {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "directory" => Ok(Self::Directory),
+            "count" => Ok(Self::Count),
+            _ => Err(GeneratorError::InvalidSharding(s.to_string())),
+        }
+    }
+}
+
+/// The most contract variants a `Source` enum can hold: enums have at most 256 members, two of
+/// which are `NONE` and `TransparentUpgradeableProxy`.
+pub const MAX_VARIANTS: usize = 254;
 
 pub struct GeneratorOptions {
     pub library_name: String,
     pub license: String,
     pub solidity_version: String,
+    pub enum_policy: EnumPolicy,
+    /// Alias contracts declared more than once under path-derived names instead of failing.
+    pub disambiguate: bool,
+    /// The ordinals of the `Source` variants. Variants are sorted by name if `None`.
+    pub lock: Option<SourceLock>,
+    /// Generate `fromString(string)`, the reverse of `toString(Source)`.
+    pub from_string: bool,
+    /// Generate `all()` and `count()`, enumerating the contract variants.
+    pub enumeration: bool,
+    /// Generate `isDeployable(Source)`.
+    pub is_deployable: bool,
+    /// The init code hashes of the contract variants by identifier, from the build artifacts.
+    /// `initCodeHash(Source)` and `predictAddress` are generated if `Some`.
+    pub init_code_hashes: Option<BTreeMap<String, [u8; 32]>>,
+    /// The namespace mixed into the salts of `toSalt`.
+    pub salt_namespace: SaltNamespace,
+    /// Split the `Source` enum over several libraries behind a dispatcher.
+    pub sharding: Sharding,
+    /// The most variants per library with [`Sharding::Count`].
+    pub shard_size: usize,
+    /// The template the library is rendered from.
+    pub template: LibraryTemplate,
+    /// The template the dispatcher of sharded libraries is rendered from.
+    pub dispatcher_template: LibraryTemplate,
+    /// The paths passed on to the template.
+    pub paths: TemplatePaths,
 }
 
 impl Default for GeneratorOptions {
@@ -14,88 +128,760 @@ impl Default for GeneratorOptions {
             library_name: "Sources".to_string(),
             license: "MIT".to_string(),
             solidity_version: "^0.8.0".to_string(),
+            enum_policy: EnumPolicy::default(),
+            disambiguate: false,
+            lock: None,
+            from_string: true,
+            enumeration: true,
+            is_deployable: true,
+            init_code_hashes: None,
+            salt_namespace: SaltNamespace::default(),
+            sharding: Sharding::default(),
+            shard_size: 200,
+            template: LibraryTemplate::default(),
+            dispatcher_template: LibraryTemplate::dispatcher(),
+            paths: TemplatePaths::default(),
         }
     }
 }
 
-pub fn generate_sources_library(contracts: &[ContractInfo], options: &GeneratorOptions) -> String {
-    let imports = generate_imports(contracts);
-    let enum_variants = generate_enum_variants(contracts);
-    let to_creation_code = generate_to_creation_code(contracts);
-    let to_string = generate_to_string(contracts);
+/// The `solidity_version` that [`resolve_pragma`] replaces with the tightest compatible pragma.
+pub const AUTO_PRAGMA: &str = "auto";
 
-    format!(
-        r#"// SPDX-License-Identifier: {license}
-pragma solidity {version};
+/// The compiler versions the generated code itself needs (`string.concat` is available since
+/// 0.8.12).
+pub(crate) const LIBRARY_PRAGMA: &str = "^0.8.12";
 
-import {{ ShortStrings, ShortString }} from "@openzeppelin/contracts/utils/ShortStrings.sol";
+/// Check `options.solidity_version` against the pragmas of the files the library imports.
+///
+/// `imports` is the graph of the files of the imported contracts, whose own imports are compiled
+/// along with the library too (see [`ImportGraph::from_files`]).
+///
+/// Returns the pragma to generate: `solidity_version` itself, or the tightest requirement
+/// satisfied by the library and every imported file if it is [`AUTO_PRAGMA`].
+pub fn resolve_pragma(
+    contracts: &[ContractInfo],
+    imports: &ImportGraph,
+    options: &GeneratorOptions,
+) -> Result<String, GeneratorError> {
+    let pragmas: Vec<_> = contracts
+        .iter()
+        .filter(|c| options.enum_policy.includes(c.kind))
+        .filter_map(|c| Some((c.import_path.as_str(), c.pragma.as_ref()?)))
+        .chain(imports.pragmas.iter().map(|(path, pragma)| (path.as_str(), pragma)))
+        .collect();
+    let required =
+        pragmas.iter().fold(LIBRARY_PRAGMA.parse::<VersionReq>()?, |required, (_, pragma)| {
+            required.intersect(pragma)
+        });
 
-{imports}
+    if required.is_empty() {
+        let mut paths: Vec<_> =
+            pragmas.iter().map(|(path, pragma)| format!("{path} ({pragma})")).collect();
+        paths.sort();
+        paths.dedup();
+        return Err(GeneratorError::NoCompatiblePragma { paths });
+    }
 
-/// @title {library_name}
-/// @notice Auto-generated reflection library for contract metadata
-/// @dev Generated by solidity-reflections
-library {library_name} {{
-    using ShortStrings for ShortString;
+    if options.solidity_version == AUTO_PRAGMA {
+        return Ok(required.to_string());
+    }
 
-    bytes32 public constant NICKNAMED_PROXY_FLAG = keccak256("NICKNAMED_PROXY");
-    bytes32 public constant EIP4337_FLAG = keccak256("EIP4337");
+    let requested: VersionReq = options.solidity_version.parse()?;
+    if requested.intersect(&required).is_empty() {
+        return Err(GeneratorError::IncompatiblePragma {
+            requested: options.solidity_version.clone(),
+            required: required.to_string(),
+        });
+    }
+    Ok(options.solidity_version.clone())
+}
 
-    enum Source {{
-        NONE,
-        TransparentUpgradeableProxy,
-{enum_variants}
-    }}
+/// A unified diff from the `current` contents of `path` to the `generated` ones, `None` if they
+/// are identical.
+pub fn diff_library(path: &str, current: &str, generated: &str) -> Option<String> {
+    if current == generated {
+        return None;
+    }
+    let diff = TextDiff::from_lines(current, generated)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string();
+    Some(diff)
+}
 
-    error UnknownMetaArtifact();
+/// A contract together with the identifier it is imported and enumerated as.
+#[derive(Clone)]
+struct Variant<'a> {
+    ident: String,
+    /// The name the contract is imported as, since `ident` names its `Source` constant.
+    alias: String,
+    contract: &'a ContractInfo,
+}
 
-    function toSalt(Source metaArtifact) internal pure returns (bytes32) {{
-        return keccak256(abi.encodePacked(toString(metaArtifact)));
-    }}
+/// The identifiers of the `Source` variants generated for `contracts`, sorted by name.
+pub fn variant_names(
+    contracts: &[ContractInfo],
+    options: &GeneratorOptions,
+) -> Result<Vec<String>, GeneratorError> {
+    Ok(source_variants(contracts, options)?.into_iter().map(|(ident, _)| ident).collect())
+}
+
+/// The contracts getting a `Source` variant, together with the identifier of the variant (which
+/// is also the name `toString` returns), sorted by name.
+pub fn source_variants<'a>(
+    contracts: &'a [ContractInfo],
+    options: &GeneratorOptions,
+) -> Result<Vec<(String, &'a ContractInfo)>, GeneratorError> {
+    let contracts = variants(
+        contracts.iter().filter(|c| options.enum_policy.includes(c.kind)).collect(),
+        options.disambiguate,
+    )?;
+    Ok(contracts.into_iter().map(|v| (v.ident, v.contract)).collect())
+}
+
+pub fn generate_sources_library(
+    contracts: &[ContractInfo],
+    options: &GeneratorOptions,
+) -> Result<String, GeneratorError> {
+    let known = contracts;
+    let contracts = variants(
+        contracts.iter().filter(|c| options.enum_policy.includes(c.kind)).collect(),
+        options.disambiguate,
+    )?;
+    // new variants are appended to the lock, like `shards` does
+    let lock = match &options.lock {
+        Some(lock) => {
+            let mut lock = lock.clone();
+            lock.update(&contracts.iter().map(|v| v.ident.clone()).collect::<Vec<_>>())?;
+            Some(lock)
+        }
+        None => None,
+    };
+    let count = lock.as_ref().map_or(contracts.len(), |lock| lock.variants.len());
+    if count > MAX_VARIANTS {
+        return Err(GeneratorError::TooManyVariants {
+            library: options.library_name.clone(),
+            count,
+        });
+    }
+    render_library(&contracts, known, options, &options.library_name, lock.as_ref())
+}
+
+/// A generated library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedLibrary {
+    pub library_name: String,
+    pub code: String,
+}
+
+/// Generate the libraries for `options.sharding`: the library itself, or the dispatcher
+/// followed by the shard libraries. Shard libraries go next to the dispatcher, in
+/// `<library_name>.s.sol`.
+pub fn generate_libraries(
+    contracts: &[ContractInfo],
+    options: &GeneratorOptions,
+) -> Result<Vec<GeneratedLibrary>, GeneratorError> {
+    if options.sharding == Sharding::None {
+        return Ok(vec![GeneratedLibrary {
+            library_name: options.library_name.clone(),
+            code: generate_sources_library(contracts, options)?,
+        }]);
+    }
+
+    let known = contracts;
+    let contracts = variants(
+        contracts.iter().filter(|c| options.enum_policy.includes(c.kind)).collect(),
+        options.disambiguate,
+    )?;
+    let shards = shards(&contracts, options)?;
+
+    let mut libraries = vec![GeneratedLibrary {
+        library_name: options.library_name.clone(),
+        code: render_dispatcher(&contracts, &shards, options)?,
+    }];
+    for shard in &shards {
+        let members: Vec<_> = contracts
+            .iter()
+            .filter(|v| shard.lock.variants.iter().any(|l| !l.removed && l.name == v.ident))
+            .cloned()
+            .collect();
+        libraries.push(GeneratedLibrary {
+            library_name: shard.library_name.clone(),
+            code: render_library(&members, known, options, &shard.library_name, Some(&shard.lock))?,
+        });
+    }
+    Ok(libraries)
+}
+
+/// The directory shard of each variant, empty unless sharding by directory.
+pub fn variant_shards(
+    contracts: &[ContractInfo],
+    options: &GeneratorOptions,
+) -> Result<BTreeMap<String, String>, GeneratorError> {
+    if options.sharding != Sharding::Directory {
+        return Ok(BTreeMap::new());
+    }
+    let contracts = variants(
+        contracts.iter().filter(|c| options.enum_policy.includes(c.kind)).collect(),
+        options.disambiguate,
+    )?;
+    Ok(contracts
+        .iter()
+        .map(|v| (v.ident.clone(), directory_of(&v.contract.import_path, options)))
+        .collect())
+}
+
+/// A library holding part of the `Source` variants.
+struct Shard {
+    id: usize,
+    library_name: String,
+    /// The variants of the shard with their ordinals in the shard enum.
+    lock: SourceLock,
+}
+
+/// Split the variants into shards, keeping the ordinals of `options.lock`.
+fn shards(
+    contracts: &[Variant<'_>],
+    options: &GeneratorOptions,
+) -> Result<Vec<Shard>, GeneratorError> {
+    let mut lock = options.lock.clone().unwrap_or_default();
+    let names: Vec<String> = contracts.iter().map(|v| v.ident.clone()).collect();
+    lock.update(&names)?;
+
+    let groups: Vec<(usize, String, Vec<LockedVariant>)> = match options.sharding {
+        Sharding::None => unreachable!("unsharded libraries have no shards"),
+        Sharding::Count => {
+            if !(1..=MAX_VARIANTS).contains(&options.shard_size) {
+                return Err(GeneratorError::InvalidShardSize(options.shard_size));
+            }
+            lock.variants
+                .chunks(options.shard_size)
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let entries = chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(j, entry)| LockedVariant {
+                            ordinal: FIRST_ORDINAL + j,
+                            ..entry.clone()
+                        })
+                        .collect();
+                    (i + 1, format!("{}{}", options.library_name, i + 1), entries)
+                })
+                .collect()
+        }
+        Sharding::Directory => {
+            // the ordinals within each shard are recorded in the lock, so that contracts moving
+            // between directories do not renumber the others
+            lock.assign_shards(
+                &contracts
+                    .iter()
+                    .map(|v| (v.ident.clone(), directory_of(&v.contract.import_path, options)))
+                    .collect(),
+            );
+            let mut directories = lock.shards.clone();
+            directories.sort();
+
+            let mut names = HashSet::new();
+            let mut groups: Vec<_> = directories
+                .into_iter()
+                .filter_map(|directory| {
+                    let id = lock.shard_id(&directory)?;
+                    let mut name = format!("{}{}", options.library_name, pascal_case(&directory));
+                    if !names.insert(name.clone()) {
+                        name = format!("{name}{id}");
+                    }
+                    Some((id, name, lock.shard_variants(&directory)))
+                })
+                .collect();
+            groups.sort_by_key(|(id, _, _)| *id);
+            groups
+        }
+    };
+
+    groups
+        .into_iter()
+        .map(|(id, library_name, entries)| {
+            if entries.len() > MAX_VARIANTS {
+                return Err(GeneratorError::TooManyVariants {
+                    library: library_name,
+                    count: entries.len(),
+                });
+            }
+            let mut lock = SourceLock::default();
+            lock.variants = entries;
+            Ok(Shard { id, library_name, lock })
+        })
+        .collect()
+}
+
+/// The directory shard of a contract: the top-level directory of the contracts directory it is
+/// declared in, the contracts directory itself for files directly inside it, or the first
+/// segment of the import path for dependencies.
+fn directory_of(import_path: &str, options: &GeneratorOptions) -> String {
+    let contracts_dir = options.paths.contracts_dir.trim_end_matches('/');
+    match import_path.strip_prefix(contracts_dir).and_then(|p| p.strip_prefix('/')) {
+        Some(relative) => relative
+            .split_once('/')
+            .map_or_else(|| contracts_dir.to_string(), |(dir, _)| dir.to_string()),
+        None => import_path.split('/').next().unwrap_or_default().to_string(),
+    }
+}
 
-    function toSalt(Source metaArtifact, ShortString nickname) internal pure returns (bytes32) {{
-        return keccak256(abi.encodePacked(toString(metaArtifact), nickname.toString()));
+/// `PascalCase` of a directory name, e.g. `Openzeppelin` for `@openzeppelin` and `MyTokens` for
+/// `my-tokens`.
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+                .collect::<String>()
+        })
+        .collect()
+}
+
+fn render_library(
+    contracts: &[Variant<'_>],
+    known: &[ContractInfo],
+    options: &GeneratorOptions,
+    library_name: &str,
+    lock: Option<&SourceLock>,
+) -> Result<String, GeneratorError> {
+    options.salt_namespace.validate()?;
+    let mut context = template_context(contracts, options, library_name);
+    context.sections = TemplateSections {
+        imports: generate_imports(contracts),
+        enum_variants: generate_enum_variants(contracts, lock),
+        constants: generate_constants(contracts),
+        to_salt: generate_to_salt(&options.salt_namespace),
+        to_creation_code: generate_to_creation_code(contracts),
+        to_creation_code_with_args: generate_to_creation_code_with_args(contracts, known),
+        to_string: generate_to_string(contracts),
+        from_string: if options.from_string {
+            generate_from_string(contracts)
+        } else {
+            String::new()
+        },
+        enumeration: if options.enumeration {
+            generate_enumeration(contracts, lock)
+        } else {
+            String::new()
+        },
+        is_deployable: if options.is_deployable {
+            generate_is_deployable(contracts)
+        } else {
+            String::new()
+        },
+        init_code_hash: match &options.init_code_hashes {
+            Some(hashes) => generate_init_code_hash(contracts, hashes, &options.salt_namespace),
+            None => String::new(),
+        },
+    };
+    options.template.render(&context)
+}
+
+fn template_context(
+    contracts: &[Variant<'_>],
+    options: &GeneratorOptions,
+    library_name: &str,
+) -> TemplateContext {
+    TemplateContext {
+        options: TemplateOptions {
+            library_name: library_name.to_string(),
+            license: options.license.clone(),
+            solidity_version: options.solidity_version.clone(),
+            enum_policy: options.enum_policy.as_str().to_string(),
+            disambiguate: options.disambiguate,
+            from_string: options.from_string,
+            enumeration: options.enumeration,
+            is_deployable: options.is_deployable,
+            init_code_hashes: options.init_code_hashes.is_some(),
+            salt_namespace: options.salt_namespace.id().unwrap_or_default(),
+            salt_chain_id: options.salt_namespace.chain_id,
+        },
+        paths: options.paths.clone(),
+        contracts: contracts
+            .iter()
+            .map(|v| TemplateContract {
+                ident: v.ident.clone(),
+                alias: v.alias.clone(),
+                name: v.contract.name.clone(),
+                import_path: v.contract.import_path.clone(),
+                kind: v.contract.kind.to_string(),
+                deployable: v.contract.kind.is_deployable(),
+                constructor_params: v
+                    .contract
+                    .constructor_params
+                    .iter()
+                    .map(|p| p.type_name.clone())
+                    .collect(),
+            })
+            .collect(),
+        shards: Vec::new(),
+        sections: TemplateSections::default(),
+    }
+}
+
+/// Render the dispatcher, whose `Source` values are `(shard << 8) | ordinal`.
+fn render_dispatcher(
+    contracts: &[Variant<'_>],
+    shards: &[Shard],
+    options: &GeneratorOptions,
+) -> Result<String, GeneratorError> {
+    options.salt_namespace.validate()?;
+    let active = |shard: &Shard| -> Vec<(String, usize)> {
+        shard
+            .lock
+            .variants
+            .iter()
+            .filter(|l| !l.removed)
+            .map(|l| (l.name.clone(), l.ordinal))
+            .collect()
+    };
+
+    let imports = shards
+        .iter()
+        .map(|s| format!("import {{ {} }} from \"./{}.s.sol\";", s.library_name, s.library_name))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let constants = shards
+        .iter()
+        .flat_map(|shard| {
+            active(shard).into_iter().map(move |(ident, ordinal)| {
+                format!(
+                    "    Source internal constant {ident} = Source.wrap(({} << 8) | {ordinal});",
+                    shard.id
+                )
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let conversions = shards
+        .iter()
+        .map(|shard| {
+            format!(
+                "    function fromShard({0}.Source source) internal pure returns (Source) {{\n        return Source.wrap(({1} << 8) | uint256(source));\n    }}",
+                shard.library_name, shard.id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    // calls `<Shard>.<function>(<Shard>.Source(ordinal))` in the shard of `metaArtifact`
+    let dispatch = |function: &str| -> String {
+        shards
+            .iter()
+            .map(|shard| {
+                format!(
+                    "        if (shard == {}) {{\n            return {1}.{function}({1}.Source(ordinal));\n        }}",
+                    shard.id, shard.library_name
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let to_creation_code = format!(
+        r#"    function toCreationCode(Source metaArtifact) internal pure returns (bytes memory) {{
+        (uint256 shard, uint256 ordinal) = shardOf(metaArtifact);
+{}
+        revert UnknownMetaArtifact();
+    }}"#,
+        dispatch("toCreationCode")
+    );
+    let to_string = format!(
+        r#"    function toString(Source metaArtifact) internal pure returns (string memory) {{
+        if (Source.unwrap(metaArtifact) == Source.unwrap(TransparentUpgradeableProxy)) {{
+            return "TransparentUpgradeableProxy";
+        }}
+        (uint256 shard, uint256 ordinal) = shardOf(metaArtifact);
+{}
+        revert UnknownMetaArtifact();
+    }}"#,
+        dispatch("toString")
+    );
+
+    let idents: Vec<String> =
+        shards.iter().flat_map(|shard| active(shard).into_iter().map(|(ident, _)| ident)).collect();
+    let from_string = if options.from_string {
+        let cases = idents
+            .iter()
+            .map(|ident| {
+                format!(
+                    "        if (nameHash == keccak256(\"{ident}\")) {{\n            return {ident};\n        }}"
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            r#"    function fromString(string memory name) internal pure returns (Source) {{
+        bytes32 nameHash = keccak256(bytes(name));
+        if (nameHash == keccak256("TransparentUpgradeableProxy")) {{
+            return TransparentUpgradeableProxy;
+        }}
+{cases}
+        revert UnknownMetaArtifact();
+    }}"#
+        )
+    } else {
+        String::new()
+    };
+    let enumeration = if options.enumeration {
+        let items = idents
+            .iter()
+            .enumerate()
+            .map(|(i, ident)| format!("        sources[{i}] = {ident};"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let count = idents.len();
+        format!(
+            r#"    function all() internal pure returns (Source[] memory sources) {{
+        sources = new Source[]({count});
+{items}
     }}
 
-    function getFullNicknamedName(Source metaArtifact, ShortString nickname) internal pure returns (string memory) {{
-        return string.concat(string.concat(toString(metaArtifact), "_"), nickname.toString());
+    function count() internal pure returns (uint256) {{
+        return {count};
+    }}"#
+        )
+    } else {
+        String::new()
+    };
+    let is_deployable = if options.is_deployable {
+        format!(
+            r#"    function isDeployable(Source metaArtifact) internal pure returns (bool) {{
+        (uint256 shard, uint256 ordinal) = shardOf(metaArtifact);
+{}
+        return false;
+    }}"#,
+            dispatch("isDeployable")
+        )
+    } else {
+        String::new()
+    };
+
+    let init_code_hash = if options.init_code_hashes.is_some() {
+        format!(
+            r#"    function initCodeHash(Source metaArtifact) internal pure returns (bytes32) {{
+        (uint256 shard, uint256 ordinal) = shardOf(metaArtifact);
+{}
+        revert UnknownMetaArtifact();
     }}
 
-{to_creation_code}
+{}"#,
+            dispatch("initCodeHash"),
+            generate_predict_address(&options.salt_namespace)
+        )
+    } else {
+        String::new()
+    };
 
-{to_string}
-}}
-"#,
-        license = options.license,
-        version = options.solidity_version,
-        imports = imports,
-        library_name = options.library_name,
-        enum_variants = enum_variants,
-        to_creation_code = to_creation_code,
-        to_string = to_string,
-    )
+    let mut context = template_context(contracts, options, &options.library_name);
+    context.shards = shards
+        .iter()
+        .map(|shard| TemplateShard {
+            id: shard.id,
+            library_name: shard.library_name.clone(),
+            contracts: active(shard).into_iter().map(|(ident, _)| ident).collect(),
+        })
+        .collect();
+    context.sections = TemplateSections {
+        imports,
+        enum_variants: conversions,
+        constants,
+        to_salt: generate_to_salt(&options.salt_namespace),
+        to_creation_code,
+        to_creation_code_with_args: r#"    function toCreationCode(Source metaArtifact, bytes memory constructorArgs) internal pure returns (bytes memory) {
+        return abi.encodePacked(toCreationCode(metaArtifact), constructorArgs);
+    }"#
+        .to_string(),
+        to_string,
+        from_string,
+        enumeration,
+        is_deployable,
+        init_code_hash,
+    };
+    options.dispatcher_template.render(&context)
+}
+
+/// Assign each contract its identifier, failing on (or aliasing) names declared more than once.
+fn variants(
+    contracts: Vec<&ContractInfo>,
+    disambiguate: bool,
+) -> Result<Vec<Variant<'_>>, GeneratorError> {
+    let mut by_name: BTreeMap<&str, Vec<&ContractInfo>> = BTreeMap::new();
+    for contract in &contracts {
+        by_name.entry(&contract.name).or_default().push(contract);
+    }
+
+    let mut idents: HashMap<(&str, &str), String> = HashMap::new();
+    for (name, group) in &by_name {
+        if group.len() == 1 {
+            continue;
+        }
+        if !disambiguate {
+            return Err(duplicate_error(name, group));
+        }
+        for (contract, ident) in group.iter().zip(disambiguated_names(name, group)) {
+            idents.insert((&contract.name, &contract.import_path), ident);
+        }
+    }
+
+    let mut variants: Vec<_> = contracts
+        .into_iter()
+        .map(|contract| Variant {
+            ident: idents
+                .remove(&(contract.name.as_str(), contract.import_path.as_str()))
+                .unwrap_or_else(|| contract.name.clone()),
+            alias: String::new(),
+            contract,
+        })
+        .collect();
+
+    // aliases must not clash with other contracts either
+    let mut seen = HashSet::new();
+    for variant in &variants {
+        if !seen.insert(&variant.ident) {
+            let group: Vec<_> =
+                variants.iter().filter(|v| v.ident == variant.ident).map(|v| v.contract).collect();
+            return Err(duplicate_error(&variant.ident, &group));
+        }
+    }
+
+    // the `Source` constants would shadow the contracts imported under their own names
+    let mut taken: HashSet<String> = seen.into_iter().cloned().collect();
+    for variant in &mut variants {
+        variant.alias = format!("_{}", variant.ident);
+        while taken.contains(&variant.alias) {
+            variant.alias.insert(0, '_');
+        }
+        taken.insert(variant.alias.clone());
+    }
+    Ok(variants)
+}
+
+/// Derive unique identifiers from the directories that differ between the declaring files, e.g.
+/// `Vault` and `Vault_v2` for `src/Vault.sol` and `src/v2/Vault.sol`. Falls back to including the
+/// file name when the directories are the same.
+fn disambiguated_names(name: &str, group: &[&ContractInfo]) -> Vec<String> {
+    let components: Vec<Vec<&str>> =
+        group.iter().map(|c| c.import_path.split('/').collect()).collect();
+    let common = (0..)
+        .take_while(|&i| {
+            components.iter().all(|c| i + 1 < c.len() && c.get(i) == components[0].get(i))
+        })
+        .count();
+
+    let names_from = |with_file: bool| -> Vec<String> {
+        components
+            .iter()
+            .map(|c| {
+                let dirs = &c[common..c.len() - 1];
+                let file = c.last().map(|f| f.trim_end_matches(".sol")).into_iter();
+                let parts: Vec<_> =
+                    dirs.iter().copied().chain(file.filter(|_| with_file)).collect();
+                if parts.is_empty() {
+                    name.to_string()
+                } else {
+                    sanitize_identifier(&format!("{name}_{}", parts.join("_")))
+                }
+            })
+            .collect()
+    };
+
+    let names = names_from(false);
+    if names.iter().collect::<HashSet<_>>().len() == names.len() { names } else { names_from(true) }
+}
+
+fn sanitize_identifier(ident: &str) -> String {
+    ident.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+fn duplicate_error(name: &str, group: &[&ContractInfo]) -> GeneratorError {
+    GeneratorError::DuplicateContract {
+        name: name.to_string(),
+        paths: group.iter().map(|c| c.import_path.clone()).collect(),
+    }
 }
 
-fn generate_imports(contracts: &[ContractInfo]) -> String {
+fn generate_imports(contracts: &[Variant<'_>]) -> String {
     contracts
         .iter()
-        .map(|c| format!("import {{ {} }} from \"{}\";", c.name, c.import_path))
+        .map(|v| {
+            format!(
+                "import {{ {} as {} }} from \"{}\";",
+                v.contract.name, v.alias, v.contract.import_path
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The contract variants of the `Source` enum, in the order of `lock` if there is one.
+///
+/// The lock is updated with `contracts` first, so it has an ordinal for every variant and marks
+/// all others removed.
+fn generate_enum_variants(contracts: &[Variant<'_>], lock: Option<&SourceLock>) -> String {
+    let Some(lock) = lock else {
+        return contracts
+            .iter()
+            .map(|v| format!("        {}", v.ident))
+            .collect::<Vec<_>>()
+            .join(",\n");
+    };
+
+    let idents = lock.idents();
+    let last = idents.len().saturating_sub(1);
+    lock.variants
+        .iter()
+        .zip(idents)
+        .enumerate()
+        .map(|(i, (locked, ident))| {
+            // the comma goes before the comment
+            let separator = if i == last { "" } else { "," };
+            if locked.removed {
+                format!("        {ident}{separator} // removed, keeps the later ordinals stable")
+            } else {
+                format!("        {ident}{separator}")
+            }
+        })
         .collect::<Vec<_>>()
         .join("\n")
 }
 
-fn generate_enum_variants(contracts: &[ContractInfo]) -> String {
-    contracts.iter().map(|c| format!("        {}", c.name)).collect::<Vec<_>>().join(",\n")
+/// A `Source` constant per contract, so that sources are `Sources.<Contract>` like in the
+/// dispatcher.
+fn generate_constants(contracts: &[Variant<'_>]) -> String {
+    contracts
+        .iter()
+        .map(|v| format!("    Source internal constant {0} = Source.{0};", v.ident))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn generate_to_creation_code(contracts: &[ContractInfo]) -> String {
+fn generate_to_creation_code(contracts: &[Variant<'_>]) -> String {
     let cases = contracts
         .iter()
-        .map(|c| {
-            format!(
-                "        if (metaArtifact == Source.{}) {{\n            return type({}).creationCode;\n        }}",
-                c.name, c.name
-            )
+        .map(|v| {
+            if v.contract.kind.is_deployable() {
+                format!(
+                    "        if (metaArtifact == Source.{}) {{\n            return type({}).creationCode;\n        }}",
+                    v.ident, v.alias
+                )
+            } else {
+                format!(
+                    "        if (metaArtifact == Source.{}) {{\n            revert NonDeployableMetaArtifact(); // {}\n        }}",
+                    v.ident, v.contract.kind
+                )
+            }
         })
         .collect::<Vec<_>>()
         .join("\n");
@@ -111,13 +897,109 @@ fn generate_to_creation_code(contracts: &[ContractInfo]) -> String {
     )
 }
 
-fn generate_to_string(contracts: &[ContractInfo]) -> String {
+/// Generate creation code helpers that append ABI-encoded constructor arguments.
+///
+/// A raw `bytes` overload is always emitted. Contracts whose constructor parameters can all be
+/// expressed without their own imports also get a typed overload, shared by every contract with
+/// the same parameter types, except for a single `bytes` parameter, which the raw overload takes.
+/// Contract-typed and `address payable` parameters are taken as
+/// `address`, which encodes identically.
+fn generate_to_creation_code_with_args(
+    contracts: &[Variant<'_>],
+    known: &[ContractInfo],
+) -> String {
+    let mut overloads: BTreeMap<Vec<String>, Vec<&Variant<'_>>> = BTreeMap::new();
+    for variant in contracts {
+        let contract = variant.contract;
+        if !contract.kind.is_deployable() || contract.constructor_params.is_empty() {
+            continue;
+        }
+        let types: Option<Vec<_>> =
+            contract.constructor_params.iter().map(|p| abi_parameter_type(p, known)).collect();
+        // a single `bytes` parameter would redeclare the raw overload
+        if let Some(types) = types.filter(|types| types != &["bytes memory"]) {
+            overloads.entry(types).or_default().push(variant);
+        }
+    }
+
+    let mut functions = vec![
+        r#"    function toCreationCode(Source metaArtifact, bytes memory constructorArgs) internal pure returns (bytes memory) {
+        return abi.encodePacked(toCreationCode(metaArtifact), constructorArgs);
+    }"#
+        .to_string(),
+    ];
+
+    for (types, contracts) in overloads {
+        let params =
+            types.iter().enumerate().map(|(i, ty)| format!(", {ty} arg{i}")).collect::<String>();
+        let args = (0..types.len()).map(|i| format!("arg{i}")).collect::<Vec<_>>().join(", ");
+        let cases = contracts
+            .iter()
+            .map(|v| {
+                format!(
+                    "        if (metaArtifact == Source.{}) {{\n            return abi.encodePacked(type({}).creationCode, abi.encode({args}));\n        }}",
+                    v.ident, v.alias
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        functions.push(format!(
+            r#"    function toCreationCode(Source metaArtifact{params}) internal pure returns (bytes memory) {{
+{cases}
+        revert UnknownMetaArtifact();
+    }}"#,
+        ));
+    }
+
+    functions.join("\n\n")
+}
+
+/// The type of a constructor parameter as used in a generated helper, if it needs no import.
+fn abi_parameter_type(param: &Parameter, known: &[ContractInfo]) -> Option<String> {
+    let (base, dimensions) =
+        param.type_name.split_at(param.type_name.find('[').unwrap_or(param.type_name.len()));
+    let base = base.trim();
+    // `address payable` would make calls with payable arguments ambiguous between overloads
+    let base = if base == "address payable" || known.iter().any(|c| c.name == base) {
+        "address"
+    } else if is_elementary_type(base) {
+        base
+    } else {
+        return None;
+    };
+
+    let is_reference = !dimensions.is_empty() || base == "string" || base == "bytes";
+    Some(format!("{base}{dimensions}{}", if is_reference { " memory" } else { "" }))
+}
+
+fn is_elementary_type(name: &str) -> bool {
+    if matches!(name, "address" | "address payable" | "bool" | "string" | "bytes" | "uint" | "int")
+    {
+        return true;
+    }
+    if let Some(size) = name.strip_prefix("bytes") {
+        return size.parse().is_ok_and(|n: u32| (1..=32).contains(&n));
+    }
+    name.strip_prefix("uint")
+        .or_else(|| name.strip_prefix("int"))
+        .and_then(|bits| bits.parse::<u32>().ok())
+        .is_some_and(|bits| bits % 8 == 0 && (8..=256).contains(&bits))
+}
+
+fn generate_to_string(contracts: &[Variant<'_>]) -> String {
     let cases = contracts
         .iter()
-        .map(|c| {
+        .map(|v| {
+            // aliased contracts keep their alias so that salts and config keys stay unique
+            let name = if v.ident == v.contract.name {
+                format!("type({}).name", v.alias)
+            } else {
+                format!("\"{}\"", v.ident)
+            };
             format!(
-                "        if (metaArtifact == Source.{}) {{\n            return type({}).name;\n        }}",
-                c.name, c.name
+                "        if (metaArtifact == Source.{}) {{\n            return {name};\n        }}",
+                v.ident
             )
         })
         .collect::<Vec<_>>()
@@ -133,3 +1015,316 @@ fn generate_to_string(contracts: &[ContractInfo]) -> String {
     }}"#,
     )
 }
+
+/// Generate `fromString`, accepting the names `toString` returns.
+fn generate_from_string(contracts: &[Variant<'_>]) -> String {
+    let cases = contracts
+        .iter()
+        .map(|v| {
+            format!(
+                "        if (nameHash == keccak256(\"{}\")) {{\n            return Source.{};\n        }}",
+                v.ident, v.ident
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"    function fromString(string memory name) internal pure returns (Source) {{
+        bytes32 nameHash = keccak256(bytes(name));
+        if (nameHash == keccak256("TransparentUpgradeableProxy")) {{
+            return Source.TransparentUpgradeableProxy;
+        }}
+{cases}
+        revert UnknownMetaArtifact();
+    }}"#,
+    )
+}
+
+/// Generate `all` and `count` over the contract variants, in enum order and without tombstones.
+fn generate_enumeration(contracts: &[Variant<'_>], lock: Option<&SourceLock>) -> String {
+    let idents: Vec<&str> = match lock {
+        Some(lock) => lock
+            .variants
+            .iter()
+            .filter(|locked| !locked.removed)
+            .map(|locked| locked.name.as_str())
+            .collect(),
+        None => contracts.iter().map(|v| v.ident.as_str()).collect(),
+    };
+    let items = idents
+        .iter()
+        .enumerate()
+        .map(|(i, ident)| format!("        sources[{i}] = Source.{ident};"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let count = idents.len();
+
+    format!(
+        r#"    function all() internal pure returns (Source[] memory sources) {{
+        sources = new Source[]({count});
+{items}
+    }}
+
+    function count() internal pure returns (uint256) {{
+        return {count};
+    }}"#,
+    )
+}
+
+/// Generate `isDeployable`, true for the variants `toCreationCode` returns creation code for.
+fn generate_is_deployable(contracts: &[Variant<'_>]) -> String {
+    let cases = contracts
+        .iter()
+        .filter(|v| v.contract.kind.is_deployable())
+        .map(|v| {
+            format!(
+                "        if (metaArtifact == Source.{}) {{\n            return true;\n        }}",
+                v.ident
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"    function isDeployable(Source metaArtifact) internal pure returns (bool) {{
+{cases}
+        return false;
+    }}"#,
+    )
+}
+
+/// The state mutability of the functions deriving salts: salts with the chain id read
+/// `block.chainid`.
+fn salt_mutability(namespace: &SaltNamespace) -> &'static str {
+    if namespace.chain_id { "view" } else { "pure" }
+}
+
+/// Generate `toSalt`, hashing the namespace, the chain id, the name and the nickname.
+fn generate_to_salt(namespace: &SaltNamespace) -> String {
+    let mut prefix = String::new();
+    let mut constant = String::new();
+    if namespace.id().is_some() {
+        constant = format!(
+            "    bytes32 public constant SALT_NAMESPACE = keccak256(abi.encode(string(\"{}\"), string(\"{}\")));\n\n",
+            namespace.project, namespace.version
+        );
+        prefix.push_str("SALT_NAMESPACE, ");
+    }
+    if namespace.chain_id {
+        prefix.push_str("block.chainid, ");
+    }
+    let mutability = salt_mutability(namespace);
+
+    format!(
+        r#"{constant}    function toSalt(Source metaArtifact) internal {mutability} returns (bytes32) {{
+        return keccak256(abi.encodePacked({prefix}toString(metaArtifact)));
+    }}
+
+    function toSalt(Source metaArtifact, ShortString nickname) internal {mutability} returns (bytes32) {{
+        return keccak256(abi.encodePacked({prefix}toString(metaArtifact), nickname.toString()));
+    }}"#,
+    )
+}
+
+/// Generate `predictAddress`, the CREATE2 address of a source deployed by `deployer` with its
+/// salt.
+fn generate_predict_address(namespace: &SaltNamespace) -> String {
+    let mutability = salt_mutability(namespace);
+    format!(
+        r#"    function predictAddress(Source metaArtifact, address deployer) internal {mutability} returns (address) {{
+        bytes32 hash = keccak256(abi.encodePacked(bytes1(0xff), deployer, toSalt(metaArtifact), initCodeHash(metaArtifact)));
+        return address(uint160(uint256(hash)));
+    }}
+
+    function predictAddress(Source metaArtifact, address deployer, ShortString nickname) internal {mutability} returns (address) {{
+        bytes32 hash = keccak256(abi.encodePacked(bytes1(0xff), deployer, toSalt(metaArtifact, nickname), initCodeHash(metaArtifact)));
+        return address(uint160(uint256(hash)));
+    }}"#,
+    )
+}
+
+/// Generate `initCodeHash` with the hashes of the artifacts, and `predictAddress` on top of it.
+/// Variants without a hash revert, like `toCreationCode` does for variants without creation code.
+fn generate_init_code_hash(
+    contracts: &[Variant<'_>],
+    hashes: &BTreeMap<String, [u8; 32]>,
+    namespace: &SaltNamespace,
+) -> String {
+    let cases = contracts
+        .iter()
+        .map(|v| {
+            let body = match hashes.get(&v.ident) {
+                // the init code includes the constructor arguments, which the hash cannot know
+                _ if !v.contract.constructor_params.is_empty() => {
+                    "revert UnknownMetaArtifact(); // takes constructor arguments".to_string()
+                }
+                Some(hash) => format!("return 0x{};", encode_hex(hash)),
+                None if !v.contract.kind.is_deployable() => {
+                    format!("revert NonDeployableMetaArtifact(); // {}", v.contract.kind)
+                }
+                None => "revert UnknownMetaArtifact(); // no fixed creation code in the artifacts"
+                    .to_string(),
+            };
+            format!(
+                "        if (metaArtifact == Source.{}) {{\n            {body}\n        }}",
+                v.ident
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"    function initCodeHash(Source metaArtifact) internal pure returns (bytes32) {{
+{cases}
+        revert UnknownMetaArtifact();
+    }}
+
+{}"#,
+        generate_predict_address(namespace)
+    )
+}
+
+/// The sources whose `initCodeHash` differs between the `current` and the `generated` library,
+/// e.g. because the contracts were rebuilt since the library was generated.
+pub fn stale_init_code_hashes(current: &str, generated: &str) -> Vec<String> {
+    let current = init_code_hashes_of(current);
+    init_code_hashes_of(generated)
+        .into_iter()
+        .filter(|(ident, hash)| current.get(ident).is_some_and(|current| current != hash))
+        .map(|(ident, _)| ident)
+        .collect()
+}
+
+/// The hashes `initCodeHash` returns in a generated library, by identifier.
+fn init_code_hashes_of(library: &str) -> BTreeMap<String, String> {
+    let mut hashes = BTreeMap::new();
+    let mut in_function = false;
+    let mut ident = None;
+    for line in library.lines().map(str::trim) {
+        if line.starts_with("function ") {
+            in_function = line.starts_with("function initCodeHash(Source metaArtifact)");
+        } else if let Some(rest) = line.strip_prefix("if (metaArtifact == Source.") {
+            ident = rest.strip_suffix(") {");
+        } else if let Some(hash) = line.strip_prefix("return 0x").and_then(|l| l.strip_suffix(';')) &&
+            in_function &&
+            let Some(ident) = ident
+        {
+            hashes.insert(ident.to_string(), hash.to_string());
+        }
+    }
+    hashes
+}
+
+/// Generate a Foundry test deploying every deployable source through `StdConfigBasedWiring`
+/// with the debug configuration, asserting that it gets code at its predicted CREATE2 address.
+///
+/// The test imports the library from `options.paths.output` and the wiring from `di_dir`, the
+/// directory `reflections init` scaffolds. Plain wiring deploys without arguments, so sources
+/// with constructor parameters are deployed by the test itself, with zero values passed through
+/// `toCreationCode`; those whose parameter types need their own imports are skipped. With
+/// `options.init_code_hashes`, the wired addresses are also checked against `predictAddress`.
+pub fn generate_smoke_tests(
+    contracts: &[ContractInfo],
+    options: &GeneratorOptions,
+    di_dir: &str,
+) -> Result<String, GeneratorError> {
+    let library = &options.library_name;
+    let hashes = options.init_code_hashes.as_ref();
+    let tests = source_variants(contracts, options)?
+        .into_iter()
+        .filter(|(_, contract)| contract.kind.is_deployable())
+        .map(|(ident, contract)| {
+            let source = format!("{library}.{ident}");
+            let types: Option<Vec<_>> = contract
+                .constructor_params
+                .iter()
+                .map(|p| abi_parameter_type(p, contracts))
+                .collect();
+            let body = match types {
+                Some(types) if !types.is_empty() => {
+                    // uninitialized locals hold the zero value of their type
+                    let locals = types
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| format!("        {ty} arg{i};\n"))
+                        .collect::<String>();
+                    let args =
+                        (0..types.len()).map(|i| format!("arg{i}")).collect::<Vec<_>>().join(", ");
+                    // the dispatcher and a single `bytes` parameter only have the raw overload
+                    let creation_code = if options.sharding == Sharding::None
+                        && types != ["bytes memory"]
+                    {
+                        format!("{library}.toCreationCode({source}, {args})")
+                    } else {
+                        format!("{library}.toCreationCode({source}, abi.encode({args}))")
+                    };
+                    format!(
+                        "        // plain wiring does not pass constructor arguments\n{locals}        assertDeployed({source}, {creation_code});"
+                    )
+                }
+                None => format!(
+                    "        // {ident} takes constructor arguments whose types need their own imports\n        vm.skip(true);"
+                ),
+                _ if hashes.is_some_and(|hashes| hashes.contains_key(&ident)) => format!(
+                    "        address deployed = assertWired({source});\n        assertEq(deployed, {library}.predictAddress({source}, address(wiring)), \"predictAddress\");"
+                ),
+                _ => format!("        assertWired({source});"),
+            };
+            format!("    function test_{ident}() public {{\n{body}\n    }}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(format!(
+        r#"// SPDX-License-Identifier: {license}
+pragma solidity {version};
+
+import {{ Test }} from "forge-std/Test.sol";
+
+import {{ {library} }} from "{output}";
+import {{ IWiringMechanism }} from "{di_dir}/interfaces/IWiringMechanism.s.sol";
+import {{ StdConfigBasedWiring }} from "{di_dir}/wiring/StdConfigBasedWiring.s.sol";
+
+/// @title {library}SmokeTest
+/// @notice Auto-generated smoke tests deploying every deployable source with the debug configuration
+/// @dev Generated by solidity-reflections
+contract {library}SmokeTest is Test {{
+    using {library} for {library}.Source;
+
+    StdConfigBasedWiring internal wiring;
+
+    function setUp() public {{
+        wiring = new StdConfigBasedWiring();
+    }}
+
+    function assertWired({library}.Source source) internal returns (address deployed) {{
+        string memory name = source.toString();
+        bytes32 initCodeHash = keccak256(source.toCreationCode());
+        address predicted = vm.computeCreate2Address(source.toSalt(), initCodeHash, address(wiring));
+
+        deployed = wiring.wire(abi.encode(source), IWiringMechanism.SupportedWiring.PLAIN);
+        assertGt(deployed.code.length, 0, name);
+        assertEq(deployed, predicted, name);
+        assertEq(wiring.getWiredVariants(abi.encode(source), IWiringMechanism.SupportedWiring.PLAIN)[0], deployed, name);
+    }}
+
+    function assertDeployed({library}.Source source, bytes memory creationCode) internal returns (address deployed) {{
+        string memory name = source.toString();
+        bytes32 salt = source.toSalt();
+        address predicted = vm.computeCreate2Address(salt, keccak256(creationCode), address(this));
+
+        assembly {{
+            deployed := create2(0, add(creationCode, 0x20), mload(creationCode), salt)
+        }}
+        assertGt(deployed.code.length, 0, name);
+        assertEq(deployed, predicted, name);
+    }}
+{tests_block}}}
+"#,
+        license = options.license,
+        version = options.solidity_version,
+        output = options.paths.output,
+        tests_block = if tests.is_empty() { String::new() } else { format!("\n{tests}\n") },
+    ))
+}
@@ -0,0 +1,256 @@
+//! File-level import graph of a Solidity project
+//!
+//! Import paths are resolved the way Foundry does: relative imports (`./`, `../`) against the
+//! importing file, everything else through the project remappings, then against the project root
+//! and finally inside the dependency directories (`libs` in foundry.toml).
+
+use crate::{
+    Result,
+    config::Paths,
+    errors::ImportError,
+    model::ProjectModel,
+    parser::{ImportDirective, SourceUnit, parse_source_unit},
+    pragma::VersionReq,
+    remappings::Remappings,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// Resolves import paths to files, relative to the project root.
+#[derive(Debug, Clone)]
+pub struct ImportResolver {
+    root: PathBuf,
+    remappings: Remappings,
+    /// The dependency directories, relative to the project root.
+    libs: Vec<String>,
+}
+
+impl ImportResolver {
+    /// Create a resolver using the remappings and dependency directories of a project.
+    pub fn new(paths: &Paths) -> Result<Self> {
+        Ok(Self::with_remappings(paths, Remappings::load(paths)?))
+    }
+
+    /// Create a resolver using the dependency directories of a project and the given remappings.
+    pub fn with_remappings(paths: &Paths, remappings: Remappings) -> Self {
+        Self {
+            root: paths.root.clone(),
+            remappings,
+            libs: paths.libs.iter().map(|lib| paths.relative_to_root(lib)).collect(),
+        }
+    }
+
+    /// The project root the resolved paths are relative to.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve an import of `file` (relative to the project root) to an existing file.
+    ///
+    /// Returns the imported file relative to the project root, e.g. `lib/forge-std/src/Test.sol`.
+    pub fn resolve(&self, file: &str, import: &str) -> Option<String> {
+        self.candidates(file, import).into_iter().find(|path| self.root.join(path).is_file())
+    }
+
+    /// The path to import a file (relative to the project root) by, through the remapping
+    /// targeting it if there is one.
+    pub fn import_path(&self, file: &str) -> String {
+        self.remappings.unmap(file).unwrap_or_else(|| file.to_string())
+    }
+
+    /// The paths an import could refer to, in the order they are tried.
+    fn candidates(&self, file: &str, import: &str) -> Vec<String> {
+        if import.starts_with("./") || import.starts_with("../") {
+            let dir = file.rsplit_once('/').map_or("", |(dir, _)| dir);
+            return normalize(&format!("{dir}/{import}")).into_iter().collect();
+        }
+
+        let mut candidates: Vec<_> = [self.remappings.remap(import), import.to_string()]
+            .iter()
+            .filter_map(|p| normalize(p))
+            .collect();
+        for lib in &self.libs {
+            candidates.extend(normalize(&format!("{lib}/{import}")));
+            // Foundry remaps `<name>/` to `<lib>/<name>/src/` for dependencies without remappings
+            if let Some((name, rest)) = import.split_once('/') {
+                candidates.extend(normalize(&format!("{lib}/{name}/src/{rest}")));
+            }
+        }
+        candidates.dedup();
+        candidates
+    }
+
+    /// The diagnostics for the imports of `file` that do not resolve.
+    pub fn check(&self, file: &str, imports: &[ImportDirective]) -> Vec<ImportError> {
+        imports
+            .iter()
+            .filter(|import| self.resolve(file, &import.path).is_none())
+            .map(|import| unresolved(file, import))
+            .collect()
+    }
+
+    /// Read and parse a file.
+    fn read_unit(&self, file: &str) -> std::result::Result<SourceUnit, ImportError> {
+        let content = fs::read_to_string(self.root.join(file)).map_err(|e| {
+            ImportError::Unreadable { file: file.to_string(), reason: e.to_string() }
+        })?;
+        parse_source_unit(&content)
+            .map_err(|source| ImportError::Unparsable { file: file.to_string(), source })
+    }
+}
+
+/// Which files import which, for a set of files and everything they import transitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportGraph {
+    /// The resolved imports of each file, all paths relative to the project root.
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+    /// The `pragma solidity` requirement of each file declaring one.
+    pub pragmas: BTreeMap<String, VersionReq>,
+    /// The imports that could not be resolved and the files that could not be read.
+    pub diagnostics: Vec<ImportError>,
+}
+
+impl ImportGraph {
+    /// Build the graph of the files of `model` and every file they import.
+    pub fn build(model: &ProjectModel, resolver: &ImportResolver) -> Self {
+        Self::walk(
+            model.files.iter().map(|file| {
+                let unit = (file.unit.imports.clone(), file.unit.pragma.clone());
+                (file.import_path.clone(), Some(unit))
+            }),
+            resolver,
+        )
+    }
+
+    /// Build the graph of the Solidity files in `dir` (relative to the project root) and every
+    /// file they import.
+    pub fn from_dir(dir: &str, resolver: &ImportResolver) -> Self {
+        let mut files: Vec<_> = WalkDir::new(resolver.root.join(dir))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "sol"))
+            .filter_map(|e| {
+                let path = e.path().strip_prefix(&resolver.root).ok()?;
+                Some(path.display().to_string().replace('\\', "/"))
+            })
+            .collect();
+        files.sort();
+        Self::from_files(files, resolver)
+    }
+
+    /// Build the graph of `files` (relative to the project root) and every file they import.
+    pub fn from_files(files: impl IntoIterator<Item = String>, resolver: &ImportResolver) -> Self {
+        Self::walk(files.into_iter().map(|file| (file, None)), resolver)
+    }
+
+    fn walk(
+        files: impl Iterator<Item = (String, Option<(Vec<ImportDirective>, Option<VersionReq>)>)>,
+        resolver: &ImportResolver,
+    ) -> Self {
+        let mut graph = Self::default();
+        let mut queue: VecDeque<_> = files.collect();
+
+        while let Some((file, unit)) = queue.pop_front() {
+            if graph.edges.contains_key(&file) {
+                continue;
+            }
+            let read = || resolver.read_unit(&file).map(|unit| (unit.imports, unit.pragma));
+            let imports = match unit.map_or_else(read, Ok) {
+                Ok((imports, pragma)) => {
+                    if let Some(pragma) = pragma {
+                        graph.pragmas.insert(file.clone(), pragma);
+                    }
+                    imports
+                }
+                Err(diagnostic) => {
+                    graph.diagnostics.push(diagnostic);
+                    graph.edges.insert(file, BTreeSet::new());
+                    continue;
+                }
+            };
+
+            let mut dependencies = BTreeSet::new();
+            for import in &imports {
+                match resolver.resolve(&file, &import.path) {
+                    Some(dependency) => {
+                        if !graph.edges.contains_key(&dependency) {
+                            queue.push_back((dependency.clone(), None));
+                        }
+                        dependencies.insert(dependency);
+                    }
+                    None => graph.diagnostics.push(unresolved(&file, import)),
+                }
+            }
+            graph.edges.insert(file, dependencies);
+        }
+
+        graph
+    }
+
+    /// The files imported directly by `file`.
+    pub fn dependencies(&self, file: &str) -> impl Iterator<Item = &str> {
+        self.edges.get(file).into_iter().flatten().map(String::as_str)
+    }
+
+    /// Whether `file` imports a file matching `target`, directly or transitively.
+    pub fn depends_on(&self, file: &str, target: impl Fn(&str) -> bool) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<_> = self.dependencies(file).collect();
+        while let Some(dependency) = queue.pop_front() {
+            if target(dependency) {
+                return true;
+            }
+            if seen.insert(dependency) {
+                queue.extend(self.dependencies(dependency));
+            }
+        }
+        false
+    }
+
+    /// The files importing `file` directly.
+    pub fn dependents<'a>(&'a self, file: &'a str) -> impl Iterator<Item = &'a str> {
+        self.edges
+            .iter()
+            .filter(move |(_, dependencies)| dependencies.contains(file))
+            .map(|(dependent, _)| dependent.as_str())
+    }
+}
+
+fn unresolved(file: &str, import: &ImportDirective) -> ImportError {
+    ImportError::Unresolved {
+        file: file.to_string(),
+        import: import.path.clone(),
+        line: import.line,
+    }
+}
+
+/// Resolve `.` and `..` segments, returning `None` for paths leaving the project root.
+fn normalize(path: &str) -> Option<String> {
+    let mut segments = Vec::new();
+    for segment in Path::new(path).iter().filter_map(|s| s.to_str()) {
+        match segment {
+            "." | "/" | "" => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("src/./a/../B.sol").as_deref(), Some("src/B.sol"));
+        assert_eq!(normalize("/src/B.sol").as_deref(), Some("src/B.sol"));
+        assert_eq!(normalize("src/../../B.sol"), None);
+    }
+}
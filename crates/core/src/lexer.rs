@@ -0,0 +1,253 @@
+//! Tokenizer for Solidity source files
+//!
+//! The lexer only knows enough about Solidity to find declaration boundaries reliably: comments
+//! are dropped (doc comments are kept aside for NatSpec), string literals are kept as single
+//! tokens and every other symbol is emitted as one punctuation token per character.
+
+use crate::errors::ParseError;
+use std::ops::Range;
+
+/// The category of a [`Token`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// An identifier or keyword (e.g. `contract`, `Counter`, `uint256`).
+    Identifier,
+    /// A number literal, including hex numbers and version-like literals such as `0.8.0`.
+    Number,
+    /// A string literal, including its quotes.
+    StringLiteral,
+    /// A single punctuation character (e.g. `{`, `;`, `^`).
+    Punctuation,
+}
+
+/// A token borrowed from the source it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The raw text of the token.
+    pub text: &'a str,
+    /// The byte range of the token in the source.
+    pub span: Range<usize>,
+    /// The 1-based line the token starts on.
+    pub line: usize,
+}
+
+impl Token<'_> {
+    /// Check whether the token is the given identifier or keyword.
+    pub fn is_ident(&self, ident: &str) -> bool {
+        self.kind == TokenKind::Identifier && self.text == ident
+    }
+
+    /// Check whether the token is the given punctuation character.
+    pub fn is_punct(&self, punct: char) -> bool {
+        self.kind == TokenKind::Punctuation && self.text.chars().eq([punct])
+    }
+}
+
+/// A `///` or `/** */` comment, borrowed from the source it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocComment<'a> {
+    /// The raw text of the comment, including its markers.
+    pub text: &'a str,
+    /// The 1-based line the comment starts on.
+    pub line: usize,
+    /// The index of the first token after the comment, i.e. the one it documents.
+    pub next_token: usize,
+}
+
+/// Split Solidity source code into tokens, skipping whitespace and comments.
+pub fn tokenize(source: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    tokenize_with_doc_comments(source).map(|(tokens, _)| tokens)
+}
+
+/// Split Solidity source code into tokens like [`tokenize`], also returning the doc comments.
+pub fn tokenize_with_doc_comments(
+    source: &str,
+) -> Result<(Vec<Token<'_>>, Vec<DocComment<'_>>), ParseError> {
+    Lexer { source, bytes: source.as_bytes(), pos: 0, line: 1 }.run()
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn run(mut self) -> Result<(Vec<Token<'a>>, Vec<DocComment<'a>>), ParseError> {
+        let mut tokens = Vec::new();
+        let mut doc_comments = Vec::new();
+
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            let start = self.pos;
+            let line = self.line;
+
+            let kind = match byte {
+                b'\n' => {
+                    self.line += 1;
+                    self.pos += 1;
+                    continue;
+                }
+                b if b.is_ascii_whitespace() => {
+                    self.pos += 1;
+                    continue;
+                }
+                b'/' if self.peek(1) == Some(b'/') => {
+                    self.skip_line_comment();
+                    // `///` but not `////...`
+                    if self.source[start..self.pos].starts_with("///") &&
+                        !self.source[start..self.pos].starts_with("////")
+                    {
+                        let text = &self.source[start..self.pos];
+                        doc_comments.push(DocComment { text, line, next_token: tokens.len() });
+                    }
+                    continue;
+                }
+                b'/' if self.peek(1) == Some(b'*') => {
+                    self.skip_block_comment()?;
+                    // `/** */` but not `/**/` or `/***...`
+                    let text = &self.source[start..self.pos];
+                    if text.starts_with("/**") && !text.starts_with("/***") && text != "/**/" {
+                        doc_comments.push(DocComment { text, line, next_token: tokens.len() });
+                    }
+                    continue;
+                }
+                b'"' | b'\'' => {
+                    self.read_string(byte)?;
+                    TokenKind::StringLiteral
+                }
+                b if is_ident_start(b) => {
+                    self.pos += 1;
+                    while self.bytes.get(self.pos).is_some_and(|&b| is_ident_continue(b)) {
+                        self.pos += 1;
+                    }
+                    TokenKind::Identifier
+                }
+                b if b.is_ascii_digit() => {
+                    self.pos += 1;
+                    while self
+                        .bytes
+                        .get(self.pos)
+                        .is_some_and(|&b| is_ident_continue(b) || b == b'.')
+                    {
+                        self.pos += 1;
+                    }
+                    TokenKind::Number
+                }
+                _ => {
+                    // advance a whole UTF-8 character so spans stay on char boundaries
+                    let len = self.source[start..].chars().next().map_or(1, char::len_utf8);
+                    self.pos += len;
+                    TokenKind::Punctuation
+                }
+            };
+
+            tokens.push(Token {
+                kind,
+                text: &self.source[start..self.pos],
+                span: start..self.pos,
+                line,
+            });
+        }
+
+        Ok((tokens, doc_comments))
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    fn skip_line_comment(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(|&b| b != b'\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), ParseError> {
+        let line = self.line;
+        self.pos += 2;
+        loop {
+            match self.bytes.get(self.pos) {
+                None => return Err(ParseError::UnterminatedComment { line }),
+                Some(b'*') if self.peek(1) == Some(b'/') => {
+                    self.pos += 2;
+                    return Ok(());
+                }
+                Some(b'\n') => self.line += 1,
+                Some(_) => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn read_string(&mut self, quote: u8) -> Result<(), ParseError> {
+        let line = self.line;
+        self.pos += 1;
+        loop {
+            match self.bytes.get(self.pos) {
+                None | Some(b'\n') => return Err(ParseError::UnterminatedString { line }),
+                Some(b'\\') => self.pos += 2,
+                Some(&b) if b == quote => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+}
+
+fn is_ident_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$'
+}
+
+fn is_ident_continue(byte: u8) -> bool {
+    is_ident_start(byte) || byte.is_ascii_digit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<&str> {
+        tokenize(source).unwrap().into_iter().map(|t| t.text).collect()
+    }
+
+    #[test]
+    fn test_tokenize_skips_comments() {
+        let source = "// contract Line\n/* contract Block\n */ contract Real {}";
+        assert_eq!(texts(source), ["contract", "Real", "{", "}"]);
+    }
+
+    #[test]
+    fn test_tokenize_doc_comments() {
+        let source =
+            "/// @title A\n//// not a doc\n/** @notice B */ contract C {\n/**/ /*** no */ }";
+        let (tokens, docs) = tokenize_with_doc_comments(source).unwrap();
+        let docs: Vec<_> = docs.iter().map(|d| (d.text, d.line, d.next_token)).collect();
+        assert_eq!(docs, [("/// @title A", 1, 0), ("/** @notice B */", 3, 0)]);
+        assert_eq!(tokens.len(), 4);
+    }
+
+    #[test]
+    fn test_tokenize_string_literals() {
+        let tokens = tokenize(r#"string s = "contract \"Fake\" {"; bytes b = 'x';"#).unwrap();
+        let strings: Vec<_> =
+            tokens.iter().filter(|t| t.kind == TokenKind::StringLiteral).map(|t| t.text).collect();
+        assert_eq!(strings, [r#""contract \"Fake\" {""#, "'x'"]);
+    }
+
+    #[test]
+    fn test_tokenize_tracks_lines() {
+        let tokens = tokenize("/* a\nb */\ncontract\n\nFoo").unwrap();
+        assert_eq!(tokens[0].line, 3);
+        assert_eq!(tokens[1].line, 5);
+    }
+
+    #[test]
+    fn test_tokenize_unterminated() {
+        assert!(matches!(tokenize("/* open"), Err(ParseError::UnterminatedComment { line: 1 })));
+        assert!(matches!(tokenize("\n\"open"), Err(ParseError::UnterminatedString { line: 2 })));
+    }
+}
@@ -4,9 +4,20 @@ pub use errors::ReflectionsError;
 
 pub type Result<T> = std::result::Result<T, ReflectionsError>;
 
+pub mod artifacts;
+pub mod cache;
 pub mod config;
+pub mod create2;
 pub mod errors;
 pub mod generator;
+pub mod imports;
+pub mod lexer;
+pub mod lockfile;
+pub mod model;
+pub mod natspec;
 pub mod parser;
+pub mod pragma;
 pub mod remappings;
+pub mod selectors;
+pub mod template;
 pub mod utils;
@@ -0,0 +1,464 @@
+//! Stable ordinals for the generated `Source` enum
+//!
+//! Scripts pass `uint256(source)` into wiring encodings, so a variant has to keep its ordinal when
+//! contracts are added or removed. `reflections.lock` records the ordinal of every variant ever
+//! generated: new variants are appended, and removed ones stay behind as tombstones.
+
+use crate::{
+    Result,
+    errors::{ConfigError, GeneratorError},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+/// The location of the lockfile, relative to the project root.
+pub const LOCKFILE_PATH: &str = "reflections.lock";
+
+/// The ordinal of the first contract variant, after `NONE` and `TransparentUpgradeableProxy`.
+pub const FIRST_ORDINAL: usize = 2;
+
+/// The prefix of the placeholder variant keeping the ordinal of a removed contract.
+pub const TOMBSTONE_PREFIX: &str = "Removed_";
+
+const LOCK_FORMAT: u32 = 1;
+
+const HEADER: &str = "# This file is generated by `reflections generate` to keep the ordinals of the\n# `Source` enum stable. Commit it, and do not edit it by hand.\n\n";
+
+/// The ordinals of the `Source` enum variants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLock {
+    version: u32,
+    /// The directory shards in the order they were first generated, see
+    /// [`crate::generator::Sharding::Directory`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shards: Vec<String>,
+    /// Every variant generated so far, sorted by ordinal.
+    #[serde(default, rename = "variant")]
+    pub variants: Vec<LockedVariant>,
+    /// The tombstones variants left in their directory shards when they moved to another one.
+    #[serde(default, rename = "moved", skip_serializing_if = "Vec::is_empty")]
+    pub moved: Vec<MovedVariant>,
+}
+
+/// A variant of the `Source` enum and its ordinal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedVariant {
+    pub name: String,
+    pub ordinal: usize,
+    /// Whether the contract is gone and the variant only keeps the later ordinals in place.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+    /// The directory shard of the variant, kept for tombstones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<String>,
+    /// The ordinal of the variant in the `Source` enum of its directory shard.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard_ordinal: Option<usize>,
+}
+
+/// A variant that moved to another directory shard, keeping its ordinal in the old one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MovedVariant {
+    pub name: String,
+    pub shard: String,
+    pub shard_ordinal: usize,
+}
+
+impl LockedVariant {
+    /// The enum identifier of the variant, e.g. `Vault` or `Removed_Vault` for a tombstone.
+    pub fn ident(&self) -> String {
+        if self.removed { format!("{TOMBSTONE_PREFIX}{}", self.name) } else { self.name.clone() }
+    }
+}
+
+impl Default for SourceLock {
+    fn default() -> Self {
+        Self { version: LOCK_FORMAT, shards: Vec::new(), variants: Vec::new(), moved: Vec::new() }
+    }
+}
+
+impl SourceLock {
+    /// Load the lockfile, `None` if it does not exist.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        let lock: Self =
+            toml_edit::de::from_str(&content).map_err(ConfigError::DeserializeError)?;
+        lock.validate()?;
+        Ok(Some(lock))
+    }
+
+    /// Write the lockfile.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// The contents of the lockfile, as [`Self::save`] writes them.
+    pub fn to_toml(&self) -> Result<String> {
+        let content =
+            toml_edit::ser::to_string_pretty(self).map_err(ConfigError::SerializeError)?;
+        Ok(format!("{HEADER}{content}"))
+    }
+
+    /// Recover the ordinals from the `enum Source` of a previously generated library, so that
+    /// adopting the lockfile does not reorder an existing enum.
+    pub fn from_library(source: &str) -> Option<Self> {
+        let start = source.find("enum Source {")? + "enum Source {".len();
+        let body = &source[start..start + source[start..].find('}')?];
+        let idents: Vec<_> = body
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or_default())
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|ident| !ident.is_empty())
+            .collect();
+
+        let variants = idents
+            .iter()
+            .skip(FIRST_ORDINAL)
+            .enumerate()
+            .map(|(i, ident)| {
+                let (name, removed) = match ident.strip_prefix(TOMBSTONE_PREFIX) {
+                    Some(name) => (name, true),
+                    None => (*ident, false),
+                };
+                LockedVariant {
+                    name: name.to_string(),
+                    ordinal: FIRST_ORDINAL + i,
+                    removed,
+                    shard: None,
+                    shard_ordinal: None,
+                }
+            })
+            .collect();
+        Some(Self { variants, ..Self::default() })
+    }
+
+    /// Record the variants of a new generation.
+    ///
+    /// Locked variants keep their ordinals (a tombstone is revived when its contract comes back),
+    /// variants missing from `names` become tombstones, and new ones are appended in the given
+    /// order.
+    pub fn update(&mut self, names: &[String]) -> std::result::Result<(), GeneratorError> {
+        self.validate()?;
+
+        let current: HashSet<_> = names.iter().collect();
+        for variant in &mut self.variants {
+            variant.removed = !current.contains(&variant.name);
+        }
+        for name in names {
+            if !self.variants.iter().any(|v| &v.name == name) {
+                let ordinal = FIRST_ORDINAL + self.variants.len();
+                self.variants.push(LockedVariant {
+                    name: name.clone(),
+                    ordinal,
+                    removed: false,
+                    shard: None,
+                    shard_ordinal: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Record the directory shard of each variant in `shards` (variant to shard). Shards seen
+    /// for the first time are appended, so the ids of existing shards do not change.
+    ///
+    /// Variants keep their ordinal in their shard. A variant moving to another shard leaves a
+    /// tombstone in the old one and is appended to the new one, or gets its ordinal back if it
+    /// returns to a shard it left.
+    pub fn assign_shards(&mut self, shards: &BTreeMap<String, String>) {
+        // locks written before shard ordinals were recorded number every shard in lock order
+        for i in 0..self.variants.len() {
+            if let Some(shard) = &self.variants[i].shard &&
+                self.variants[i].shard_ordinal.is_none()
+            {
+                self.variants[i].shard_ordinal = Some(self.next_shard_ordinal(shard));
+            }
+        }
+
+        for i in 0..self.variants.len() {
+            let Some(shard) = shards.get(&self.variants[i].name) else { continue };
+            if self.variants[i].shard.as_ref() == Some(shard) {
+                continue;
+            }
+            let name = self.variants[i].name.clone();
+            if let (Some(old), Some(shard_ordinal)) =
+                (self.variants[i].shard.take(), self.variants[i].shard_ordinal.take())
+            {
+                self.moved.push(MovedVariant { name: name.clone(), shard: old, shard_ordinal });
+            }
+            let returned = self.moved.iter().position(|m| m.name == name && &m.shard == shard);
+            let shard_ordinal = match returned {
+                Some(i) => self.moved.remove(i).shard_ordinal,
+                None => self.next_shard_ordinal(shard),
+            };
+            self.variants[i].shard = Some(shard.clone());
+            self.variants[i].shard_ordinal = Some(shard_ordinal);
+            if !self.shards.contains(shard) {
+                self.shards.push(shard.clone());
+            }
+        }
+    }
+
+    /// The ordinal the next variant appended to a directory shard gets.
+    fn next_shard_ordinal(&self, shard: &str) -> usize {
+        self.variants
+            .iter()
+            .filter(|v| v.shard.as_deref() == Some(shard))
+            .filter_map(|v| v.shard_ordinal)
+            .chain(self.moved.iter().filter(|m| m.shard == shard).map(|m| m.shard_ordinal))
+            .max()
+            .map_or(FIRST_ORDINAL, |ordinal| ordinal + 1)
+    }
+
+    /// The variants of a directory shard with their ordinals in the shard enum, tombstones of
+    /// removed and moved variants included, sorted by ordinal.
+    pub fn shard_variants(&self, shard: &str) -> Vec<LockedVariant> {
+        let mut variants: Vec<LockedVariant> = self
+            .variants
+            .iter()
+            .filter(|v| v.shard.as_deref() == Some(shard))
+            .filter_map(|v| Some(LockedVariant { ordinal: v.shard_ordinal?, ..v.clone() }))
+            .chain(self.moved.iter().filter(|m| m.shard == shard).map(|m| LockedVariant {
+                name: m.name.clone(),
+                ordinal: m.shard_ordinal,
+                removed: true,
+                shard: Some(m.shard.clone()),
+                shard_ordinal: Some(m.shard_ordinal),
+            }))
+            .collect();
+        variants.sort_by_key(|v| v.ordinal);
+        variants
+    }
+
+    /// The enum identifiers of the variants, in order.
+    ///
+    /// Tombstones get a numeric suffix when their identifier is taken, e.g. `Removed_Vault_2`
+    /// next to a contract named `Removed_Vault`.
+    pub fn idents(&self) -> Vec<String> {
+        let mut taken: HashSet<String> =
+            self.variants.iter().filter(|v| !v.removed).map(|v| v.name.clone()).collect();
+        self.variants
+            .iter()
+            .map(|variant| {
+                if !variant.removed {
+                    return variant.name.clone();
+                }
+                let mut ident = variant.ident();
+                let mut suffix = 1;
+                while taken.contains(&ident) {
+                    suffix += 1;
+                    ident = format!("{}_{suffix}", variant.ident());
+                }
+                taken.insert(ident.clone());
+                ident
+            })
+            .collect()
+    }
+
+    /// The id of a directory shard, starting at 1, `None` if it is not locked yet.
+    pub fn shard_id(&self, shard: &str) -> Option<usize> {
+        self.shards.iter().position(|s| s == shard).map(|i| i + 1)
+    }
+
+    /// Check that the ordinals are the consecutive positions of the variants, so that no
+    /// generation can reorder them.
+    fn validate(&self) -> std::result::Result<(), GeneratorError> {
+        if self.version != LOCK_FORMAT {
+            return Err(GeneratorError::InvalidLock(format!(
+                "unsupported version {}, expected {LOCK_FORMAT}",
+                self.version
+            )));
+        }
+
+        let mut names = HashSet::new();
+        for (i, variant) in self.variants.iter().enumerate() {
+            if variant.ordinal != FIRST_ORDINAL + i {
+                return Err(GeneratorError::InvalidLock(format!(
+                    "`{}` has ordinal {}, expected {}",
+                    variant.name,
+                    variant.ordinal,
+                    FIRST_ORDINAL + i
+                )));
+            }
+            if !names.insert(&variant.name) {
+                return Err(GeneratorError::InvalidLock(format!(
+                    "`{}` is listed more than once",
+                    variant.name
+                )));
+            }
+            if let Some(shard) = variant.shard.as_ref().filter(|s| !self.shards.contains(s)) {
+                return Err(GeneratorError::InvalidLock(format!(
+                    "`{}` belongs to shard `{shard}`, which is not listed in `shards`",
+                    variant.name
+                )));
+            }
+        }
+        for moved in &self.moved {
+            if !self.shards.contains(&moved.shard) {
+                return Err(GeneratorError::InvalidLock(format!(
+                    "`{}` moved from shard `{}`, which is not listed in `shards`",
+                    moved.name, moved.shard
+                )));
+            }
+        }
+        for shard in &self.shards {
+            let ordinals: Vec<_> = self.shard_variants(shard).iter().map(|v| v.ordinal).collect();
+            if ordinals.iter().enumerate().any(|(i, ordinal)| *ordinal != FIRST_ORDINAL + i) {
+                return Err(GeneratorError::InvalidLock(format!(
+                    "the ordinals of shard `{shard}` are not consecutive"
+                )));
+            }
+        }
+        if self.shards.iter().collect::<HashSet<_>>().len() != self.shards.len() {
+            return Err(GeneratorError::InvalidLock("a shard is listed more than once".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn idents(lock: &SourceLock) -> Vec<String> {
+        lock.idents()
+    }
+
+    #[test]
+    fn test_update_keeps_ordinals() {
+        let mut lock = SourceLock::default();
+        lock.update(&names(&["Token", "Vault"])).unwrap();
+        assert_eq!(idents(&lock), ["Token", "Vault"]);
+
+        // new contracts are appended even if they sort first
+        lock.update(&names(&["Adapter", "Token", "Vault"])).unwrap();
+        assert_eq!(idents(&lock), ["Token", "Vault", "Adapter"]);
+
+        // removed contracts leave a tombstone, and get their ordinal back when they return
+        lock.update(&names(&["Adapter", "Vault"])).unwrap();
+        assert_eq!(idents(&lock), ["Removed_Token", "Vault", "Adapter"]);
+        lock.update(&names(&["Adapter", "Token", "Vault"])).unwrap();
+        assert_eq!(idents(&lock), ["Token", "Vault", "Adapter"]);
+        assert_eq!(lock.variants[2].ordinal, 4);
+    }
+
+    #[test]
+    fn test_tombstone_collisions() {
+        let mut lock = SourceLock::default();
+        lock.update(&names(&["Vault", "Removed_Vault", "Removed_Vault_2"])).unwrap();
+        lock.update(&names(&["Removed_Vault", "Removed_Vault_2"])).unwrap();
+        assert_eq!(idents(&lock), ["Removed_Vault_3", "Removed_Vault", "Removed_Vault_2"]);
+
+        // a removed `Removed_Vault` keeps its own tombstone
+        lock.update(&names(&["Removed_Vault_2"])).unwrap();
+        assert_eq!(idents(&lock), ["Removed_Vault", "Removed_Removed_Vault", "Removed_Vault_2"]);
+    }
+
+    #[test]
+    fn test_invalid_lock() {
+        let mut lock = SourceLock::default();
+        lock.update(&names(&["Token", "Vault"])).unwrap();
+        lock.variants.swap(0, 1);
+        assert!(matches!(lock.update(&names(&["Token"])), Err(GeneratorError::InvalidLock(_))));
+
+        let mut lock = SourceLock::default();
+        lock.update(&names(&["Token"])).unwrap();
+        lock.variants.push(LockedVariant {
+            name: "Token".to_string(),
+            ordinal: 3,
+            removed: true,
+            shard: None,
+            shard_ordinal: None,
+        });
+        assert!(matches!(lock.update(&names(&["Token"])), Err(GeneratorError::InvalidLock(_))));
+    }
+
+    #[test]
+    fn test_assign_shards() {
+        let mut lock = SourceLock::default();
+        lock.update(&names(&["Token", "Vault"])).unwrap();
+        lock.assign_shards(&BTreeMap::from([
+            ("Token".to_string(), "tokens".to_string()),
+            ("Vault".to_string(), "core".to_string()),
+        ]));
+        assert_eq!(lock.shards, ["tokens", "core"]);
+
+        // tombstones keep their shard, and new shards get the next id
+        lock.update(&names(&["Adapter", "Vault"])).unwrap();
+        lock.assign_shards(&BTreeMap::from([
+            ("Adapter".to_string(), "adapters".to_string()),
+            ("Vault".to_string(), "core".to_string()),
+        ]));
+        assert_eq!(lock.variants[0].shard.as_deref(), Some("tokens"));
+        assert_eq!(lock.shard_id("tokens"), Some(1));
+        assert_eq!(lock.shard_id("adapters"), Some(3));
+        assert_eq!(lock.shard_id("mocks"), None);
+
+        lock.shards.pop();
+        assert!(matches!(lock.update(&names(&["Adapter"])), Err(GeneratorError::InvalidLock(_))));
+    }
+
+    #[test]
+    fn test_assign_shards_moves() {
+        let shards = |entries: &[(&str, &str)]| -> BTreeMap<String, String> {
+            entries.iter().map(|(name, shard)| (name.to_string(), shard.to_string())).collect()
+        };
+        let shard_idents = |lock: &SourceLock, shard: &str| -> Vec<(String, usize)> {
+            lock.shard_variants(shard).iter().map(|v| (v.ident(), v.ordinal)).collect()
+        };
+        let mut lock = SourceLock::default();
+        lock.update(&names(&["A1", "A2", "B1"])).unwrap();
+        lock.assign_shards(&shards(&[("A1", "a"), ("A2", "a"), ("B1", "b")]));
+        assert_eq!(shard_idents(&lock, "a"), [("A1".to_string(), 2), ("A2".to_string(), 3)]);
+
+        // a moved variant leaves a tombstone and is appended to its new shard
+        lock.assign_shards(&shards(&[("A1", "b"), ("A2", "a"), ("B1", "b")]));
+        assert_eq!(
+            shard_idents(&lock, "a"),
+            [("Removed_A1".to_string(), 2), ("A2".to_string(), 3)]
+        );
+        assert_eq!(shard_idents(&lock, "b"), [("B1".to_string(), 2), ("A1".to_string(), 3)]);
+        lock.update(&names(&["A1", "A2", "B1"])).unwrap();
+
+        // and gets its ordinal back when it returns
+        lock.assign_shards(&shards(&[("A1", "a"), ("A2", "a"), ("B1", "b")]));
+        assert_eq!(shard_idents(&lock, "a"), [("A1".to_string(), 2), ("A2".to_string(), 3)]);
+        assert_eq!(
+            shard_idents(&lock, "b"),
+            [("B1".to_string(), 2), ("Removed_A1".to_string(), 3)]
+        );
+        lock.update(&names(&["A1", "A2", "B1"])).unwrap();
+    }
+
+    #[test]
+    fn test_from_library() {
+        let source = r#"
+    enum Source {
+        NONE,
+        TransparentUpgradeableProxy,
+        Vault,
+        Removed_Token, // tombstone
+        Adapter
+    }
+"#;
+        let lock = SourceLock::from_library(source).unwrap();
+        assert_eq!(idents(&lock), ["Vault", "Removed_Token", "Adapter"]);
+        assert_eq!(lock.variants[1].ordinal, 3);
+        assert!(lock.variants[1].removed);
+        assert!(SourceLock::from_library("library Sources {}").is_none());
+    }
+}
@@ -0,0 +1,279 @@
+//! Source-level model of a Solidity project
+//!
+//! The model keeps everything the parser finds in each file, so commands can work from source
+//! without running `forge build` first.
+
+use crate::{
+    cache::DiscoveryCache,
+    errors::{GeneratorError, ReflectionsError},
+    imports::ImportResolver,
+    parser::{
+        ContractDefinition, ContractInfo, Declaration, DiscoveryFilter, SourceUnit,
+        parse_source_unit, resolve_bases,
+    },
+};
+use rayon::prelude::*;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use walkdir::WalkDir;
+
+/// A parsed Solidity file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    /// The path the file is imported by, relative to the project root (e.g. `src/Vault.sol`).
+    pub import_path: String,
+    pub unit: SourceUnit,
+}
+
+/// The declarations of all the Solidity files in a contracts directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProjectModel {
+    /// The parsed files, sorted by import path.
+    pub files: Vec<SourceFile>,
+}
+
+/// A declaration together with the file and contract it was found in.
+#[derive(Debug, Clone, Copy)]
+pub struct ScopedDeclaration<'a> {
+    pub file: &'a SourceFile,
+    /// The enclosing contract, `None` for file-level declarations.
+    pub contract: Option<&'a ContractDefinition>,
+    pub declaration: &'a Declaration,
+}
+
+impl ScopedDeclaration<'_> {
+    /// The name qualified by the enclosing contract, e.g. `Vault.Deposited`.
+    pub fn qualified_name(&self) -> String {
+        match self.contract {
+            Some(contract) => format!("{}.{}", contract.name, self.declaration.name),
+            None => self.declaration.name.clone(),
+        }
+    }
+
+    /// The source location, e.g. `src/Vault.sol:12`.
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.file.import_path, self.declaration.line)
+    }
+}
+
+/// A file outside the contracts directory whose contracts are added to the model, written
+/// `<path>` or `<path>:<contract>` (e.g.
+/// `@openzeppelin/contracts/proxy/ERC1967/ERC1967Proxy.sol:ERC1967Proxy`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// The path as it would be imported, through remappings or relative to the project root.
+    pub path: String,
+    /// The only contract of the file to add, all of them if `None`.
+    pub contract: Option<String>,
+}
+
+impl FromStr for Dependency {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, contract) = match s.rsplit_once(':') {
+            Some((path, contract)) => (path, Some(contract)),
+            None => (s, None),
+        };
+        if path.is_empty() || contract.is_some_and(|c| c.is_empty() || c.contains('/')) {
+            return Err(GeneratorError::InvalidDependency(s.to_string()));
+        }
+        Ok(Self { path: path.to_string(), contract: contract.map(String::from) })
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.contract {
+            Some(contract) => write!(f, "{}:{contract}", self.path),
+            None => f.write_str(&self.path),
+        }
+    }
+}
+
+impl ProjectModel {
+    /// Parse the files of `src_dir` accepted by `filter`.
+    pub fn discover(
+        root: &Path,
+        src_dir: &str,
+        filter: &DiscoveryFilter,
+    ) -> Result<Self, ReflectionsError> {
+        Self::discover_with_cache(root, src_dir, filter, None)
+    }
+
+    /// Parse the files of `src_dir` like [`ProjectModel::discover`], only parsing files missing
+    /// from `cache` or changed since they were cached.
+    pub fn discover_cached(
+        root: &Path,
+        src_dir: &str,
+        filter: &DiscoveryFilter,
+        cache: &mut DiscoveryCache,
+    ) -> Result<Self, ReflectionsError> {
+        Self::discover_with_cache(root, src_dir, filter, Some(cache))
+    }
+
+    fn discover_with_cache(
+        root: &Path,
+        src_dir: &str,
+        filter: &DiscoveryFilter,
+        mut cache: Option<&mut DiscoveryCache>,
+    ) -> Result<Self, ReflectionsError> {
+        let src_path = root.join(src_dir);
+        if !src_path.exists() {
+            return Err(ReflectionsError::IOError(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Source directory not found: {}", src_path.display()),
+            )));
+        }
+
+        let matcher = filter.matcher()?;
+        let mut files = Vec::new();
+
+        for entry in WalkDir::new(&src_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "sol"))
+        {
+            let path = entry.path();
+
+            // Calculate import path relative to src directory
+            let relative_path = path.strip_prefix(&src_path).map_err(|e| {
+                ReflectionsError::IOError(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Path error: {e}"),
+                ))
+            })?;
+            let relative_path = relative_path.display().to_string().replace('\\', "/");
+            if matcher.is_match(&relative_path) {
+                files.push((path.to_path_buf(), format!("{src_dir}/{relative_path}")));
+            }
+        }
+
+        // read and parse files in parallel, reusing the cached units of unchanged files
+        let cached = cache.as_deref();
+        let units = files
+            .into_par_iter()
+            .map(|(path, import_path)| {
+                let content = std::fs::read_to_string(&path)?;
+                let hash = cached.map(|_| DiscoveryCache::hash(&content));
+                let unit =
+                    match cached.zip(hash.as_deref()).and_then(|(c, h)| c.get(&import_path, h)) {
+                        Some(unit) => unit.clone(),
+                        None => parse_source_unit(&content).map_err(|source| {
+                            ReflectionsError::ParseError { path: path.clone(), source }
+                        })?,
+                    };
+                Ok((SourceFile { path, import_path, unit }, hash))
+            })
+            .collect::<Result<Vec<_>, ReflectionsError>>()?;
+
+        let mut files = Vec::with_capacity(units.len());
+        for (file, hash) in units {
+            if let Some((cache, hash)) = cache.as_deref_mut().zip(hash) {
+                cache.insert(file.import_path.clone(), hash, file.unit.clone());
+            }
+            files.push(file);
+        }
+        files.sort_by(|a, b| a.import_path.cmp(&b.import_path));
+
+        Ok(Self { files })
+    }
+
+    /// Parse the files of `dependencies` and add them to the model.
+    ///
+    /// The files are imported through the remapping targeting them (e.g. `@openzeppelin/...`
+    /// rather than `lib/openzeppelin-contracts/...`), and only keep the requested contract if one
+    /// is given.
+    pub fn add_dependencies(
+        &mut self,
+        dependencies: &[Dependency],
+        resolver: &ImportResolver,
+    ) -> Result<(), ReflectionsError> {
+        for dependency in dependencies {
+            let resolved = resolver
+                .resolve("", &dependency.path)
+                .ok_or_else(|| GeneratorError::UnresolvedDependency(dependency.path.clone()))?;
+            let path = resolver.root().join(&resolved);
+            let content = std::fs::read_to_string(&path)?;
+            let mut unit = parse_source_unit(&content)
+                .map_err(|source| ReflectionsError::ParseError { path: path.clone(), source })?;
+
+            if let Some(contract) = &dependency.contract {
+                unit.contracts.retain(|c| &c.name == contract);
+                if unit.contracts.is_empty() {
+                    return Err(GeneratorError::UnknownDependencyContract {
+                        contract: contract.clone(),
+                        path: dependency.path.clone(),
+                    }
+                    .into());
+                }
+            }
+
+            match self.files.iter_mut().find(|file| file.path == path) {
+                // the same file requested for several of its contracts
+                Some(file) => {
+                    for contract in unit.contracts {
+                        if !file.unit.contracts.contains(&contract) {
+                            file.unit.contracts.push(contract);
+                        }
+                    }
+                }
+                None => {
+                    let import_path = resolver.import_path(&resolved);
+                    self.files.push(SourceFile { path, import_path, unit });
+                }
+            }
+        }
+        self.files.sort_by(|a, b| a.import_path.cmp(&b.import_path));
+
+        Ok(())
+    }
+
+    /// The contract-like declarations of all files, with their parents resolved.
+    pub fn contracts(&self) -> Vec<ContractInfo> {
+        let mut contracts: Vec<_> = self
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.unit.contracts.iter().map(|definition| ContractInfo {
+                    name: definition.name.clone(),
+                    path: file.path.clone(),
+                    import_path: file.import_path.clone(),
+                    kind: definition.kind,
+                    bases: definition.bases.clone(),
+                    constructor_params: definition.constructor_params.clone(),
+                    pragma: file.unit.pragma.clone(),
+                    natspec: definition.natspec.clone(),
+                })
+            })
+            .collect();
+
+        resolve_bases(&mut contracts);
+        contracts
+            .sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.import_path.cmp(&b.import_path)));
+        contracts
+    }
+
+    /// Iterate over the file-level declarations and contract members of all files.
+    pub fn declarations(&self) -> impl Iterator<Item = ScopedDeclaration<'_>> {
+        self.files.iter().flat_map(|file| {
+            let top_level = file
+                .unit
+                .declarations
+                .iter()
+                .map(move |declaration| ScopedDeclaration { file, contract: None, declaration });
+            let members = file.unit.contracts.iter().flat_map(move |contract| {
+                contract.members.iter().map(move |declaration| ScopedDeclaration {
+                    file,
+                    contract: Some(contract),
+                    declaration,
+                })
+            });
+            top_level.chain(members)
+        })
+    }
+}
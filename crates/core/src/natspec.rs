@@ -0,0 +1,152 @@
+//! NatSpec documentation read from Solidity source
+//!
+//! Doc comments (`///` lines and `/** */` blocks) directly preceding a declaration are combined
+//! and split into tags the way `solc` does: text before the first tag is the `@notice`, and a tag
+//! runs until the next one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The NatSpec tags of a declaration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NatSpec {
+    pub title: Option<String>,
+    pub notice: Option<String>,
+    pub dev: Option<String>,
+    /// `@param` descriptions in declaration order, as `(name, description)`.
+    pub params: Vec<(String, String)>,
+    /// `@return` descriptions in declaration order.
+    pub returns: Vec<String>,
+    /// `@custom:<key>` values by key (without the `custom:` prefix).
+    pub custom: BTreeMap<String, String>,
+}
+
+impl NatSpec {
+    /// Parse the raw text of consecutive doc comments, markers included.
+    pub fn parse<'a>(comments: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut natspec = Self::default();
+        let mut current: Option<(String, String)> = None;
+
+        for line in comments.into_iter().flat_map(comment_lines) {
+            if let Some(rest) = line.strip_prefix('@') {
+                if let Some((tag, value)) = current.take() {
+                    natspec.push(&tag, value);
+                }
+                let (tag, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                current = Some((tag.to_string(), value.trim().to_string()));
+            } else {
+                let (_, value) =
+                    current.get_or_insert_with(|| ("notice".to_string(), String::new()));
+                append(value, line);
+            }
+        }
+        if let Some((tag, value)) = current {
+            natspec.push(&tag, value);
+        }
+
+        natspec
+    }
+
+    /// Whether no tag was found.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Get the value of a tag such as `@title`, `@notice`, `@dev` or `@custom:group`.
+    pub fn tag(&self, tag: &str) -> Option<&str> {
+        match tag.strip_prefix('@').unwrap_or(tag) {
+            "title" => self.title.as_deref(),
+            "notice" => self.notice.as_deref(),
+            "dev" => self.dev.as_deref(),
+            tag => self.custom.get(tag.strip_prefix("custom:")?).map(String::as_str),
+        }
+    }
+
+    fn push(&mut self, tag: &str, value: String) {
+        let value = value.trim_end();
+        match tag {
+            "title" => append(self.title.get_or_insert_default(), value),
+            "notice" => append(self.notice.get_or_insert_default(), value),
+            "dev" => append(self.dev.get_or_insert_default(), value),
+            "param" => {
+                let (name, description) =
+                    value.split_once(char::is_whitespace).unwrap_or((value, ""));
+                self.params.push((name.to_string(), description.trim().to_string()));
+            }
+            "return" => self.returns.push(value.to_string()),
+            // unknown tags such as `@author` or `@inheritdoc` are ignored
+            tag => {
+                if let Some(key) = tag.strip_prefix("custom:") {
+                    append(self.custom.entry(key.to_string()).or_default(), value);
+                }
+            }
+        }
+    }
+}
+
+/// Append a line to a multi-line tag value.
+fn append(field: &mut String, line: &str) {
+    if !field.is_empty() {
+        field.push('\n');
+    }
+    field.push_str(line);
+}
+
+/// The lines of a doc comment without markers, leading `*` and surrounding whitespace.
+fn comment_lines(comment: &str) -> Vec<&str> {
+    if let Some(line) = comment.strip_prefix("///") {
+        return vec![line.trim()];
+    }
+
+    let body = comment.strip_prefix("/**").unwrap_or(comment);
+    let body = body.strip_suffix("*/").unwrap_or(body);
+    body.lines()
+        .map(|line| {
+            let line = line.trim();
+            line.strip_prefix('*').unwrap_or(line).trim()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_comments() {
+        let natspec = NatSpec::parse([
+            "/// @title Vault",
+            "/// @notice Holds deposits",
+            "///  for many users",
+            "/// @dev Not audited",
+            "/// @custom:swagger Core",
+        ]);
+        assert_eq!(natspec.title.as_deref(), Some("Vault"));
+        assert_eq!(natspec.notice.as_deref(), Some("Holds deposits\nfor many users"));
+        assert_eq!(natspec.dev.as_deref(), Some("Not audited"));
+        assert_eq!(natspec.tag("@custom:swagger"), Some("Core"));
+        assert_eq!(natspec.tag("@custom:missing"), None);
+    }
+
+    #[test]
+    fn test_parse_block_comment() {
+        let natspec = NatSpec::parse([r#"/**
+     * Deposits `amount` tokens.
+     * @param amount The amount to deposit
+     * @param receiver Who gets the shares
+     * @return shares The minted shares
+     * @author someone
+     */"#]);
+        assert_eq!(natspec.notice.as_deref(), Some("Deposits `amount` tokens."));
+        assert_eq!(
+            natspec.params,
+            [
+                ("amount".to_string(), "The amount to deposit".to_string()),
+                ("receiver".to_string(), "Who gets the shares".to_string()),
+            ]
+        );
+        assert_eq!(natspec.returns, ["shares The minted shares"]);
+        assert!(NatSpec::parse(["/** */"]).is_empty());
+    }
+}
@@ -1,75 +1,1123 @@
-//! Solidity contract parser to extract contract names from source files
+//! Solidity declaration parser to extract contracts and their members from source files
 
-use crate::errors::ReflectionsError;
-use regex::Regex;
-use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use crate::{
+    cache::DiscoveryCache,
+    errors::{ParseError, ReflectionsError},
+    lexer::{DocComment, Token, TokenKind, tokenize_with_doc_comments},
+    model::ProjectModel,
+    natspec::NatSpec,
+    pragma::VersionReq,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ContractInfo {
     pub name: String,
     pub path: PathBuf,
     pub import_path: String,
-    pub is_library: bool,
-    pub is_interface: bool,
+    pub kind: ContractKind,
+    /// The parents listed in the `is` clause, in declaration order.
+    pub bases: Vec<BaseContract>,
+    /// The parameters of the explicit constructor, empty if there is none.
+    pub constructor_params: Vec<Parameter>,
+    /// The `pragma solidity` requirement of the declaring file, if it has one.
+    pub pragma: Option<VersionReq>,
+    /// The NatSpec of the doc comments preceding the declaration.
+    pub natspec: NatSpec,
+}
+
+impl ContractInfo {
+    /// Iterate over the direct parents that were resolved to discovered contracts.
+    pub fn resolved_bases<'a>(&self, contracts: &'a [Self]) -> impl Iterator<Item = &'a Self> {
+        self.bases.iter().filter_map(move |base| {
+            let path = base.resolved_path.as_ref()?;
+            contracts.iter().find(|c| c.name == base.contract_name() && &c.path == path)
+        })
+    }
+
+    /// Check whether this contract inherits from `base`, directly or transitively.
+    ///
+    /// Parents that could not be resolved are still matched by name, so contracts inheriting from
+    /// a dependency such as `Ownable` can be selected even though `Ownable` was not discovered.
+    pub fn inherits_from(&self, base: &str, contracts: &[Self]) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![self];
+        while let Some(contract) = pending.pop() {
+            if !visited.insert((&contract.name, &contract.path)) {
+                continue;
+            }
+            if contract.bases.iter().any(|b| b.contract_name() == base) {
+                return true;
+            }
+            pending.extend(contract.resolved_bases(contracts));
+        }
+        false
+    }
+
+    /// Follow the first resolved non-interface parent up to the top of the hierarchy.
+    ///
+    /// A contract whose resolved parents are all interfaces yields its first interface. Returns
+    /// `None` if no parent was resolved.
+    pub fn root_base<'a>(&self, contracts: &'a [Self]) -> Option<&'a Self> {
+        let mut visited = HashSet::from([(&self.name, &self.path)]);
+        let mut root = None;
+        let mut current = self;
+        while let Some(next) =
+            current.resolved_bases(contracts).find(|c| c.kind != ContractKind::Interface)
+        {
+            root = Some(next);
+            if !visited.insert((&next.name, &next.path)) {
+                break;
+            }
+            current = next;
+        }
+        root.or_else(|| self.resolved_bases(contracts).next())
+    }
+}
+
+/// A parent contract from the inheritance list of a declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseContract {
+    /// The name as written, possibly qualified (e.g. `Base` or `Lib.Base`).
+    pub name: String,
+    /// The raw base constructor arguments, if any (e.g. `msg.sender` for `Ownable(msg.sender)`).
+    pub arguments: Option<String>,
+    /// The file declaring the parent, if it was resolved to a discovered contract.
+    pub resolved_path: Option<PathBuf>,
+}
+
+/// A parameter of a function-like declaration, e.g. `uint256[] memory amounts`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parameter {
+    /// The type as written, without data location (e.g. `uint256[]`, `address payable`).
+    pub type_name: String,
+    /// The data location (`memory`, `storage` or `calldata`), if any.
+    pub location: Option<String>,
+    /// The parameter name, if any.
+    pub name: Option<String>,
+    /// Whether an event parameter is `indexed`.
+    pub indexed: bool,
+}
+
+impl BaseContract {
+    /// The unqualified name of the parent contract.
+    pub fn contract_name(&self) -> &str {
+        self.name.rsplit('.').next().unwrap_or(&self.name)
+    }
+}
+
+/// The kind of a contract-like declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ContractKind {
+    Contract,
+    AbstractContract,
+    Interface,
+    Library,
+}
+
+impl ContractKind {
+    /// The Solidity keyword(s) introducing this kind of declaration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Contract => "contract",
+            Self::AbstractContract => "abstract contract",
+            Self::Interface => "interface",
+            Self::Library => "library",
+        }
+    }
+
+    /// Whether `type(X).creationCode` is available for this kind of declaration.
+    pub fn is_deployable(&self) -> bool {
+        matches!(self, Self::Contract | Self::Library)
+    }
+}
+
+impl fmt::Display for ContractKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A top-level `contract`, `interface` or `library` declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractDefinition {
+    pub name: String,
+    pub kind: ContractKind,
+    /// The parents listed in the `is` clause, unresolved.
+    pub bases: Vec<BaseContract>,
+    /// The parameters of the explicit constructor, empty if there is none.
+    pub constructor_params: Vec<Parameter>,
+    /// The structs, enums, errors, events, value types and functions declared in the body.
+    pub members: Vec<Declaration>,
+    /// The NatSpec of the doc comments preceding the declaration.
+    pub natspec: NatSpec,
+    /// The 1-based line of the declaration keyword.
+    pub line: usize,
+}
+
+/// A struct, enum, custom error, event, user-defined value type or function declaration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    /// The NatSpec of the doc comments preceding the declaration.
+    pub natspec: NatSpec,
+    /// The 1-based line of the declaration keyword.
+    pub line: usize,
+}
+
+/// The kind of a [`Declaration`], with what is known about its signature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeclarationKind {
+    Struct {
+        fields: Vec<Parameter>,
+    },
+    Enum {
+        variants: Vec<String>,
+    },
+    Error {
+        parameters: Vec<Parameter>,
+    },
+    Event {
+        parameters: Vec<Parameter>,
+        anonymous: bool,
+    },
+    UserDefinedValueType {
+        /// The underlying elementary type (e.g. `uint256`).
+        underlying: String,
+    },
+    Function {
+        parameters: Vec<Parameter>,
+        returns: Vec<Parameter>,
+        /// `external`, `public`, `internal` or `private`, if given.
+        visibility: Option<String>,
+        /// `pure`, `view` or `payable`, if given.
+        state_mutability: Option<String>,
+    },
+}
+
+impl DeclarationKind {
+    /// The keyword introducing this kind of declaration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Struct { .. } => "struct",
+            Self::Enum { .. } => "enum",
+            Self::Error { .. } => "error",
+            Self::Event { .. } => "event",
+            Self::UserDefinedValueType { .. } => "type",
+            Self::Function { .. } => "function",
+        }
+    }
+}
+
+/// The declarations found in a single Solidity file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceUnit {
+    pub contracts: Vec<ContractDefinition>,
+    /// The structs, enums, errors, events, value types and free functions declared at file level.
+    pub declarations: Vec<Declaration>,
+    /// The `pragma solidity` requirement, combining all of them if the file has several.
+    pub pragma: Option<VersionReq>,
+    /// The import directives, in source order.
+    pub imports: Vec<ImportDirective>,
+}
+
+/// An `import` directive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportDirective {
+    /// The imported path as written, without quotes (e.g.
+    /// `@openzeppelin/contracts/token/ERC20/ERC20.sol`).
+    pub path: String,
+    /// The 1-based line of the `import` keyword.
+    pub line: usize,
+}
+
+/// Parse the top-level declarations of a Solidity source file.
+///
+/// Comments and string literals never produce declarations, and the bodies of contracts, free
+/// functions and structs are skipped as balanced blocks.
+pub fn parse_source_unit(source: &str) -> Result<SourceUnit, ParseError> {
+    let (tokens, doc_comments) = tokenize_with_doc_comments(source)?;
+    Parser { source, tokens, doc_comments, pos: 0 }.parse_source_unit()
+}
+
+/// Which Solidity files contract discovery reads.
+///
+/// Glob patterns are matched against paths relative to the contracts directory (e.g.
+/// `mocks/MockToken.sol`), so `**/mocks/**` excludes every `mocks` directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiscoveryFilter {
+    /// Only read files matching at least one of these patterns (all files if empty).
+    pub include: Vec<String>,
+    /// Never read files matching any of these patterns.
+    pub exclude: Vec<String>,
+    /// Skip Foundry test files (`*.t.sol`).
+    pub skip_tests: bool,
+    /// Skip Foundry script files (`*.s.sol`).
+    pub skip_scripts: bool,
+}
+
+impl DiscoveryFilter {
+    pub(crate) fn matcher(&self) -> Result<FileMatcher, ReflectionsError> {
+        let build = |patterns: &[String]| -> Result<GlobSet, ReflectionsError> {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                builder.add(Glob::new(pattern)?);
+            }
+            Ok(builder.build()?)
+        };
+        Ok(FileMatcher {
+            include: (!self.include.is_empty()).then(|| build(&self.include)).transpose()?,
+            exclude: build(&self.exclude)?,
+            skip_tests: self.skip_tests,
+            skip_scripts: self.skip_scripts,
+        })
+    }
+}
+
+pub(crate) struct FileMatcher {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    skip_tests: bool,
+    skip_scripts: bool,
+}
+
+impl FileMatcher {
+    pub(crate) fn is_match(&self, relative_path: &str) -> bool {
+        if (self.skip_tests && relative_path.ends_with(".t.sol")) ||
+            (self.skip_scripts && relative_path.ends_with(".s.sol"))
+        {
+            return false;
+        }
+        self.include.as_ref().is_none_or(|include| include.is_match(relative_path)) &&
+            !self.exclude.is_match(relative_path)
+    }
 }
 
 pub fn discover_contracts(
     root: &Path,
     src_dir: &str,
 ) -> Result<Vec<ContractInfo>, ReflectionsError> {
-    let src_path = root.join(src_dir);
-    if !src_path.exists() {
-        return Err(ReflectionsError::IOError(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            format!("Source directory not found: {}", src_path.display()),
-        )));
-    }
-
-    let contract_regex = Regex::new(
-        r"(?m)^\s*(contract|library|interface)\s+([A-Za-z_][A-Za-z0-9_]*)",
-    )
-    .map_err(|e| {
-        ReflectionsError::IOError(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            format!("Regex error: {e}"),
-        ))
-    })?;
-
-    let mut contracts = Vec::new();
-
-    for entry in WalkDir::new(&src_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "sol"))
-    {
-        let path = entry.path();
-        let content = std::fs::read_to_string(path)?;
-
-        for cap in contract_regex.captures_iter(&content) {
-            let contract_type = &cap[1];
-            let name = cap[2].to_string();
-
-            // Calculate import path relative to src directory
-            let relative_path = path.strip_prefix(&src_path).map_err(|e| {
-                ReflectionsError::IOError(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Path error: {e}"),
-                ))
-            })?;
-
-            let import_path = format!("{}/{}", src_dir, relative_path.display()).replace('\\', "/");
-
-            contracts.push(ContractInfo {
-                name,
-                path: path.to_path_buf(),
-                import_path,
-                is_library: contract_type == "library",
-                is_interface: contract_type == "interface",
-            });
-        }
-    }
-
-    contracts.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(contracts)
+    discover_contracts_with(root, src_dir, &DiscoveryFilter::default())
+}
+
+/// Discover contracts in the files of `src_dir` accepted by `filter`.
+pub fn discover_contracts_with(
+    root: &Path,
+    src_dir: &str,
+    filter: &DiscoveryFilter,
+) -> Result<Vec<ContractInfo>, ReflectionsError> {
+    Ok(ProjectModel::discover(root, src_dir, filter)?.contracts())
+}
+
+/// Discover contracts like [`discover_contracts_with`], only parsing files missing from `cache`
+/// or changed since they were cached.
+pub fn discover_contracts_cached(
+    root: &Path,
+    src_dir: &str,
+    filter: &DiscoveryFilter,
+    cache: &mut DiscoveryCache,
+) -> Result<Vec<ContractInfo>, ReflectionsError> {
+    Ok(ProjectModel::discover_cached(root, src_dir, filter, cache)?.contracts())
+}
+
+/// Link each parent to the discovered contract it refers to.
+///
+/// A parent declared in the same file wins; otherwise the name must be unique among the discovered
+/// contracts, and ambiguous parents are left unresolved.
+pub(crate) fn resolve_bases(contracts: &mut [ContractInfo]) {
+    let resolutions: Vec<Vec<Option<PathBuf>>> = contracts
+        .iter()
+        .map(|contract| {
+            contract
+                .bases
+                .iter()
+                .map(|base| {
+                    let candidates: Vec<_> =
+                        contracts.iter().filter(|c| c.name == base.contract_name()).collect();
+                    candidates
+                        .iter()
+                        .find(|c| c.path == contract.path)
+                        .or(if candidates.len() == 1 { candidates.first() } else { None })
+                        .map(|c| c.path.clone())
+                })
+                .collect()
+        })
+        .collect();
+
+    for (contract, paths) in contracts.iter_mut().zip(resolutions) {
+        for (base, path) in contract.bases.iter_mut().zip(paths) {
+            base.resolved_path = path;
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    doc_comments: Vec<DocComment<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse_source_unit(mut self) -> Result<SourceUnit, ParseError> {
+        let mut unit = SourceUnit::default();
+
+        while let Some(token) = self.peek() {
+            let start = self.pos;
+            let kind = match token.text {
+                "abstract" => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(t) if t.is_ident("contract") => ContractKind::AbstractContract,
+                        Some(t) => return Err(unexpected("`contract`", t)),
+                        None => return Err(ParseError::UnexpectedEof { expected: "`contract`" }),
+                    }
+                }
+                "contract" => ContractKind::Contract,
+                "interface" => ContractKind::Interface,
+                "library" => ContractKind::Library,
+                "pragma"
+                    if self.tokens.get(self.pos + 1).is_some_and(|t| t.is_ident("solidity")) =>
+                {
+                    let pragma = self.parse_pragma()?;
+                    unit.pragma = Some(match unit.pragma {
+                        Some(previous) => previous.intersect(&pragma),
+                        None => pragma,
+                    });
+                    continue;
+                }
+                "import" => {
+                    unit.imports.push(self.parse_import()?);
+                    continue;
+                }
+                _ => {
+                    match self.parse_declaration()? {
+                        Some(declaration) => unit.declarations.push(declaration),
+                        None => self.skip_item()?,
+                    }
+                    continue;
+                }
+            };
+            let mut contract = self.parse_contract(kind)?;
+            contract.natspec = self.natspec(start);
+            unit.contracts.push(contract);
+        }
+
+        Ok(unit)
+    }
+
+    /// The NatSpec of the doc comments directly preceding the token at `index`.
+    fn natspec(&self, index: usize) -> NatSpec {
+        NatSpec::parse(
+            self.doc_comments.iter().filter(|doc| doc.next_token == index).map(|doc| doc.text),
+        )
+    }
+
+    /// Parse a `pragma solidity ...;` directive, starting at `pragma`.
+    fn parse_pragma(&mut self) -> Result<VersionReq, ParseError> {
+        let line = self.advance().map_or(0, |t| t.line);
+        let start = self.advance().map_or(0, |t| t.span.end);
+        let end = loop {
+            match self.advance() {
+                Some(token) if token.is_punct(';') => break token.span.start,
+                Some(_) => {}
+                None => return Err(ParseError::UnexpectedEof { expected: "`;`" }),
+            }
+        };
+
+        let pragma = self.source[start..end].trim();
+        pragma.parse().map_err(|_| ParseError::InvalidPragma { pragma: pragma.to_string(), line })
+    }
+
+    /// Parse an import directive in any of its forms, starting at `import`.
+    fn parse_import(&mut self) -> Result<ImportDirective, ParseError> {
+        let line = self.advance().map_or(0, |t| t.line);
+        let mut path = None;
+        loop {
+            match self.advance() {
+                Some(token) if token.is_punct(';') => match path {
+                    Some(path) => return Ok(ImportDirective { path, line }),
+                    None => return Err(unexpected("import path", token)),
+                },
+                Some(token) if token.kind == TokenKind::StringLiteral && path.is_none() => {
+                    path = Some(token.text[1..token.text.len() - 1].to_string());
+                }
+                Some(_) => {}
+                None => return Err(ParseError::UnexpectedEof { expected: "`;`" }),
+            }
+        }
+    }
+
+    /// Parse a contract-like declaration, starting at its keyword.
+    fn parse_contract(&mut self, kind: ContractKind) -> Result<ContractDefinition, ParseError> {
+        let line = self.advance().map_or(0, |t| t.line);
+        let name = self.expect_ident("contract name")?.to_string();
+
+        let mut bases = Vec::new();
+        if self.peek().is_some_and(|t| t.is_ident("is")) {
+            self.pos += 1;
+            loop {
+                bases.push(self.parse_base_contract()?);
+                if !self.peek().is_some_and(|t| t.is_punct(',')) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+
+        // skip anything else up to the body, such as a storage layout specifier
+        loop {
+            match self.peek() {
+                Some(t) if t.is_punct('{') => break,
+                Some(t) if t.is_punct('(') || t.is_punct('[') => self.skip_balanced()?,
+                Some(t) if t.is_punct(';') || t.is_punct('}') => {
+                    return Err(unexpected("`{`", t));
+                }
+                Some(_) => self.pos += 1,
+                None => return Err(ParseError::UnexpectedEof { expected: "`{`" }),
+            }
+        }
+
+        let mut constructor_params = Vec::new();
+        let mut members = Vec::new();
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(t) if t.is_punct('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(t) if t.is_ident("constructor") => {
+                    self.pos += 1;
+                    constructor_params = self.parse_parameter_list()?;
+                    self.skip_item()?;
+                }
+                Some(_) => match self.parse_declaration()? {
+                    Some(declaration) => members.push(declaration),
+                    None => self.skip_item()?,
+                },
+                None => return Err(ParseError::UnexpectedEof { expected: "`}`" }),
+            }
+        }
+
+        Ok(ContractDefinition {
+            name,
+            kind,
+            bases,
+            constructor_params,
+            members,
+            natspec: NatSpec::default(),
+            line,
+        })
+    }
+
+    /// Parse a struct, enum, error, event, user-defined value type or function, starting at its
+    /// keyword. Returns `None` without consuming anything if the current item is none of these.
+    fn parse_declaration(&mut self) -> Result<Option<Declaration>, ParseError> {
+        let start = self.pos;
+        let (Some(keyword), Some(name)) = (self.peek(), self.tokens.get(self.pos + 1)) else {
+            return Ok(None);
+        };
+        if keyword.kind != TokenKind::Identifier || name.kind != TokenKind::Identifier {
+            return Ok(None);
+        }
+        let (keyword, name, line) = (keyword.text, name.text.to_string(), keyword.line);
+        if !matches!(keyword, "struct" | "enum" | "error" | "event" | "type" | "function") {
+            return Ok(None);
+        }
+        self.pos += 2;
+
+        let kind = match keyword {
+            "struct" => DeclarationKind::Struct { fields: self.parse_struct_fields()? },
+            "enum" => {
+                let body = self.parse_block()?;
+                let variants = self.tokens[body]
+                    .iter()
+                    .filter(|t| t.kind == TokenKind::Identifier)
+                    .map(|t| t.text.to_string())
+                    .collect();
+                DeclarationKind::Enum { variants }
+            }
+            "error" => {
+                let parameters = self.parse_parameter_list()?;
+                self.skip_item()?;
+                DeclarationKind::Error { parameters }
+            }
+            "event" => {
+                let parameters = self.parse_parameter_list()?;
+                let anonymous = self.peek().is_some_and(|t| t.is_ident("anonymous"));
+                self.skip_item()?;
+                DeclarationKind::Event { parameters, anonymous }
+            }
+            "type" => {
+                match self.advance() {
+                    Some(t) if t.is_ident("is") => {}
+                    Some(t) => return Err(unexpected("`is`", t)),
+                    None => return Err(ParseError::UnexpectedEof { expected: "`is`" }),
+                }
+                let start = self.peek().map_or(self.source.len(), |t| t.span.start);
+                self.skip_item()?;
+                let end = self.tokens[self.pos - 1].span.start;
+                DeclarationKind::UserDefinedValueType {
+                    underlying: self.source[start..end].trim().to_string(),
+                }
+            }
+            _ => self.parse_function()?,
+        };
+
+        Ok(Some(Declaration { name, kind, natspec: self.natspec(start), line }))
+    }
+
+    /// Parse the fields of a struct body, e.g. `{ address owner; uint256 amount; }`.
+    fn parse_struct_fields(&mut self) -> Result<Vec<Parameter>, ParseError> {
+        let body = self.parse_block()?;
+
+        let mut fields = Vec::new();
+        let mut depth = 0usize;
+        let mut field_start = body.start;
+        for i in body {
+            let token = &self.tokens[i];
+            if token.is_punct('(') || token.is_punct('[') {
+                depth += 1;
+            } else if token.is_punct(')') || token.is_punct(']') {
+                depth = depth.saturating_sub(1);
+            } else if depth == 0 && token.is_punct(';') {
+                if field_start < i {
+                    fields.push(self.parameter(&self.tokens[field_start..i]));
+                }
+                field_start = i + 1;
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Skip a `{ ... }` block, returning the index range of the tokens inside it.
+    fn parse_block(&mut self) -> Result<Range<usize>, ParseError> {
+        match self.peek() {
+            Some(t) if t.is_punct('{') => {}
+            Some(t) => return Err(unexpected("`{`", t)),
+            None => return Err(ParseError::UnexpectedEof { expected: "`{`" }),
+        }
+        let start = self.pos + 1;
+        self.skip_balanced()?;
+        Ok(start..self.pos - 1)
+    }
+
+    /// Parse the rest of a function after its name: parameters, attributes, returns and body.
+    fn parse_function(&mut self) -> Result<DeclarationKind, ParseError> {
+        let parameters = self.parse_parameter_list()?;
+        let mut returns = Vec::new();
+        let mut visibility = None;
+        let mut state_mutability = None;
+
+        loop {
+            let Some(token) = self.peek() else {
+                return Err(ParseError::UnexpectedEof { expected: "`;`" });
+            };
+            match token.text {
+                "{" => {
+                    self.skip_balanced()?;
+                    break;
+                }
+                ";" => {
+                    self.pos += 1;
+                    break;
+                }
+                "(" | "[" => self.skip_balanced()?,
+                ")" | "]" | "}" => return Err(unbalanced(token)),
+                "returns" => {
+                    self.pos += 1;
+                    returns = self.parse_parameter_list()?;
+                }
+                "external" | "public" | "internal" | "private" => {
+                    visibility = Some(token.text.to_string());
+                    self.pos += 1;
+                }
+                "pure" | "view" | "payable" => {
+                    state_mutability = Some(token.text.to_string());
+                    self.pos += 1;
+                }
+                _ => self.pos += 1,
+            }
+        }
+
+        Ok(DeclarationKind::Function { parameters, returns, visibility, state_mutability })
+    }
+
+    /// Parse a parenthesized parameter list, e.g. `(address owner, uint256[] memory amounts)`.
+    fn parse_parameter_list(&mut self) -> Result<Vec<Parameter>, ParseError> {
+        match self.peek() {
+            Some(t) if t.is_punct('(') => {}
+            Some(t) => return Err(unexpected("`(`", t)),
+            None => return Err(ParseError::UnexpectedEof { expected: "`(`" }),
+        }
+        let start = self.pos + 1;
+        self.skip_balanced()?;
+        let end = self.pos - 1;
+
+        let mut params = Vec::new();
+        let mut depth = 0usize;
+        let mut param_start = start;
+        for i in start..=end {
+            let token = &self.tokens[i];
+            if token.is_punct('(') || token.is_punct('[') {
+                depth += 1;
+            } else if depth > 0 && (token.is_punct(')') || token.is_punct(']')) {
+                depth -= 1;
+            } else if depth == 0 && (i == end || token.is_punct(',')) {
+                if param_start < i {
+                    params.push(self.parameter(&self.tokens[param_start..i]));
+                }
+                param_start = i + 1;
+            }
+        }
+        Ok(params)
+    }
+
+    /// Build a parameter from its tokens, splitting off the data location and the name.
+    fn parameter(&self, tokens: &[Token<'a>]) -> Parameter {
+        let mut type_end = tokens.len();
+        let mut name = None;
+        let mut location = None;
+
+        if let [.., prev, last] = tokens &&
+            last.kind == TokenKind::Identifier &&
+            !is_type_suffix(last.text) &&
+            !prev.is_punct('.')
+        {
+            name = Some(last.text.to_string());
+            type_end -= 1;
+        }
+        if let Some(last) = tokens[..type_end].last() &&
+            matches!(last.text, "memory" | "storage" | "calldata")
+        {
+            location = Some(last.text.to_string());
+            type_end -= 1;
+        }
+        let indexed = tokens[..type_end].last().is_some_and(|last| last.is_ident("indexed"));
+        if indexed {
+            type_end -= 1;
+        }
+
+        let type_tokens = &tokens[..type_end];
+        let type_name = match (type_tokens.first(), type_tokens.last()) {
+            (Some(first), Some(last)) => self.source[first.span.start..last.span.end].to_string(),
+            _ => String::new(),
+        };
+        Parameter { type_name, location, name, indexed }
+    }
+
+    /// Parse one entry of an inheritance list, e.g. `Lib.Base(1, 2)`.
+    fn parse_base_contract(&mut self) -> Result<BaseContract, ParseError> {
+        let mut name = self.expect_ident("base contract name")?.to_string();
+        while self.peek().is_some_and(|t| t.is_punct('.')) {
+            self.pos += 1;
+            name.push('.');
+            name.push_str(self.expect_ident("base contract name")?);
+        }
+
+        let mut arguments = None;
+        if let Some(open) = self.peek().filter(|t| t.is_punct('(')) {
+            let start = open.span.end;
+            self.skip_balanced()?;
+            let end = self.tokens[self.pos - 1].span.start;
+            arguments = Some(self.source[start..end].trim().to_string());
+        }
+
+        Ok(BaseContract { name, arguments, resolved_path: None })
+    }
+
+    /// Skip a top-level item that ends either with `;` or with a block (e.g. a free function).
+    fn skip_item(&mut self) -> Result<(), ParseError> {
+        loop {
+            let Some(token) = self.peek() else {
+                return Err(ParseError::UnexpectedEof { expected: "`;`" });
+            };
+            if token.is_punct('{') {
+                return self.skip_balanced();
+            } else if token.is_punct(';') {
+                self.pos += 1;
+                return Ok(());
+            } else if token.is_punct('(') || token.is_punct('[') {
+                self.skip_balanced()?;
+            } else if token.is_punct(')') || token.is_punct(']') || token.is_punct('}') {
+                return Err(unbalanced(token));
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip from an opening delimiter to its matching closing delimiter (inclusive).
+    fn skip_balanced(&mut self) -> Result<(), ParseError> {
+        let mut closers = Vec::new();
+        loop {
+            let Some(token) = self.advance() else {
+                let expected = match closers.last() {
+                    Some(')') => "`)`",
+                    Some(']') => "`]`",
+                    _ => "`}`",
+                };
+                return Err(ParseError::UnexpectedEof { expected });
+            };
+            if token.kind != TokenKind::Punctuation {
+                continue;
+            }
+            match token.text {
+                "{" => closers.push('}'),
+                "(" => closers.push(')'),
+                "[" => closers.push(']'),
+                "}" | ")" | "]" => {
+                    if closers.pop() != token.text.chars().next() {
+                        return Err(unbalanced(token));
+                    }
+                    if closers.is_empty() {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<&Token<'a>> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn expect_ident(&mut self, expected: &'static str) -> Result<&'a str, ParseError> {
+        match self.advance() {
+            Some(t) if t.kind == TokenKind::Identifier => Ok(t.text),
+            Some(t) => Err(unexpected(expected, t)),
+            None => Err(ParseError::UnexpectedEof { expected }),
+        }
+    }
+}
+
+/// Keywords that can end a parameter without being its name.
+fn is_type_suffix(ident: &str) -> bool {
+    matches!(ident, "memory" | "storage" | "calldata" | "payable" | "indexed")
+}
+
+fn unexpected(expected: &'static str, found: &Token<'_>) -> ParseError {
+    ParseError::UnexpectedToken { expected, found: found.text.to_string(), line: found.line }
+}
+
+fn unbalanced(token: &Token<'_>) -> ParseError {
+    ParseError::UnbalancedDelimiter {
+        delimiter: token.text.chars().next().unwrap_or_default(),
+        line: token.line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_filter() {
+        let filter = DiscoveryFilter {
+            include: vec!["**/*.sol".to_string()],
+            exclude: vec!["**/mocks/**".to_string(), "Experimental*.sol".to_string()],
+            skip_tests: true,
+            skip_scripts: false,
+        };
+        let matcher = filter.matcher().unwrap();
+        assert!(matcher.is_match("Vault.sol"));
+        assert!(matcher.is_match("core/Vault.sol"));
+        assert!(matcher.is_match("Deploy.s.sol"));
+        assert!(!matcher.is_match("mocks/MockToken.sol"));
+        assert!(!matcher.is_match("core/mocks/MockToken.sol"));
+        assert!(!matcher.is_match("ExperimentalVault.sol"));
+        assert!(!matcher.is_match("Vault.t.sol"));
+
+        let only_core =
+            DiscoveryFilter { include: vec!["core/**".to_string()], ..Default::default() };
+        let matcher = only_core.matcher().unwrap();
+        assert!(matcher.is_match("core/Vault.sol"));
+        assert!(!matcher.is_match("Vault.sol"));
+
+        let invalid = DiscoveryFilter { exclude: vec!["[".to_string()], ..Default::default() };
+        assert!(invalid.matcher().is_err());
+    }
+
+    fn names(source: &str) -> Vec<(String, ContractKind)> {
+        parse_source_unit(source).unwrap().contracts.into_iter().map(|c| (c.name, c.kind)).collect()
+    }
+
+    #[test]
+    fn test_parse_multiple_declarations() {
+        let source = r#"
+pragma solidity ^0.8.0;
+import { A } from "./A.sol";
+
+interface IToken { function f() external; }
+library SafeMath { struct S { uint256 x; } }
+contract Token is IToken, Base(1, (2)) { function f() external {} }
+"#;
+        assert_eq!(
+            names(source),
+            [
+                ("IToken".to_string(), ContractKind::Interface),
+                ("SafeMath".to_string(), ContractKind::Library),
+                ("Token".to_string(), ContractKind::Contract),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_comments_and_strings() {
+        let source = r#"
+/* contract Phantom {} */
+// library Ghost {}
+contract Real { string s = "contract Fake {"; }
+"#;
+        assert_eq!(names(source), [("Real".to_string(), ContractKind::Contract)]);
+    }
+
+    #[test]
+    fn test_parse_declarations_not_starting_a_line() {
+        let source = "struct Point { uint x; } contract A {} abstract contract B {} library C {}";
+        let names: Vec<_> = names(source).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_parse_abstract_contracts() {
+        let source =
+            "abstract contract Base { function f() public virtual; }\ncontract Impl is Base {}";
+        assert_eq!(
+            names(source),
+            [
+                ("Base".to_string(), ContractKind::AbstractContract),
+                ("Impl".to_string(), ContractKind::Contract),
+            ]
+        );
+        assert!(matches!(
+            parse_source_unit("abstract library L {}"),
+            Err(ParseError::UnexpectedToken { expected: "`contract`", .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_skips_nested_braces_and_free_functions() {
+        let source = r#"
+function free(uint a) pure returns (uint) { if (a > 0) { return a; } return 0; }
+contract Outer {
+    function inner() public { { contract_like(); } }
+}
+"#;
+        let unit = parse_source_unit(source).unwrap();
+        assert_eq!(unit.contracts.len(), 1);
+        assert_eq!(unit.contracts[0].name, "Outer");
+        assert_eq!(unit.contracts[0].line, 3);
+    }
+
+    #[test]
+    fn test_parse_pragma() {
+        let source = "pragma solidity >=0.8.0 <0.9.0;\npragma abicoder v2;\ncontract A {}";
+        let unit = parse_source_unit(source).unwrap();
+        assert_eq!(unit.pragma.unwrap().to_string(), "^0.8.0");
+        assert_eq!(unit.contracts.len(), 1);
+
+        // several pragmas in one file must all hold
+        let source = "pragma solidity ^0.8.0;\npragma solidity >=0.8.20;\ncontract A {}";
+        assert_eq!(parse_source_unit(source).unwrap().pragma.unwrap().to_string(), "^0.8.20");
+
+        assert_eq!(parse_source_unit("contract A {}").unwrap().pragma, None);
+        assert_eq!(
+            parse_source_unit("\npragma solidity latest;").unwrap_err(),
+            ParseError::InvalidPragma { pragma: "latest".to_string(), line: 2 }
+        );
+    }
+
+    #[test]
+    fn test_parse_imports() {
+        let source = r#"
+import "./Base.sol";
+import "../lib/Math.sol" as Math;
+import * as Tokens from "@openzeppelin/contracts/token/ERC20/ERC20.sol";
+import {
+    Ownable,
+    Ownable2Step as Owned
+} from 'src/access/Ownable.sol';
+contract A {}
+"#;
+        let unit = parse_source_unit(source).unwrap();
+        let imports: Vec<_> = unit.imports.iter().map(|i| (i.path.as_str(), i.line)).collect();
+        assert_eq!(
+            imports,
+            [
+                ("./Base.sol", 2),
+                ("../lib/Math.sol", 3),
+                ("@openzeppelin/contracts/token/ERC20/ERC20.sol", 4),
+                ("src/access/Ownable.sol", 5),
+            ]
+        );
+        assert_eq!(unit.contracts.len(), 1);
+        assert_eq!(
+            parse_source_unit("import {A} from;").unwrap_err(),
+            ParseError::UnexpectedToken {
+                expected: "import path",
+                found: ";".to_string(),
+                line: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_natspec() {
+        let source = r#"
+/// @title Vault
+/// @custom:swagger Core
+abstract contract Vault {
+    /** @notice Deposit tokens
+     *  @param amount The amount */
+    function deposit(uint256 amount) external {}
+
+    // not a doc comment
+    event Deposited(uint256 amount);
+}
+/// @dev Belongs to Plain
+contract Plain {}
+"#;
+        let unit = parse_source_unit(source).unwrap();
+        let vault = &unit.contracts[0];
+        assert_eq!(vault.natspec.title.as_deref(), Some("Vault"));
+        assert_eq!(vault.natspec.tag("@custom:swagger"), Some("Core"));
+        assert_eq!(vault.members[0].natspec.notice.as_deref(), Some("Deposit tokens"));
+        assert_eq!(vault.members[0].natspec.params[0].0, "amount");
+        assert!(vault.members[1].natspec.is_empty());
+        assert_eq!(unit.contracts[1].natspec.dev.as_deref(), Some("Belongs to Plain"));
+    }
+
+    #[test]
+    fn test_parse_inheritance_list() {
+        let source = "contract Token is IToken, Lib.Base, Ownable(msg.sender), Pair(f(1), (2)) {}";
+        let unit = parse_source_unit(source).unwrap();
+        let bases: Vec<_> = unit.contracts[0]
+            .bases
+            .iter()
+            .map(|b| (b.name.as_str(), b.contract_name(), b.arguments.as_deref()))
+            .collect();
+        assert_eq!(
+            bases,
+            [
+                ("IToken", "IToken", None),
+                ("Lib.Base", "Base", None),
+                ("Ownable", "Ownable", Some("msg.sender")),
+                ("Pair", "Pair", Some("f(1), (2)")),
+            ]
+        );
+        assert!(parse_source_unit("contract A is B layout at 0x10 {}").is_ok());
+        assert!(parse_source_unit("contract A is {}").is_err());
+    }
+
+    #[test]
+    fn test_parse_constructor_parameters() {
+        let source = r#"
+contract Vault is Base(1) {
+    struct Config { uint256 cap; }
+    modifier onlyOwner() { _; }
+    constructor(
+        address payable owner,
+        uint256[2] memory limits,
+        Lib.Config memory config,
+        mapping_like,
+        bytes calldata
+    ) Base(2) payable {}
+    function constructorLike() public {}
+}
+contract Plain { function f(uint a) public {} }
+"#;
+        let unit = parse_source_unit(source).unwrap();
+        let params: Vec<_> = unit.contracts[0]
+            .constructor_params
+            .iter()
+            .map(|p| (p.type_name.as_str(), p.location.as_deref(), p.name.as_deref()))
+            .collect();
+        assert_eq!(
+            params,
+            [
+                ("address payable", None, Some("owner")),
+                ("uint256[2]", Some("memory"), Some("limits")),
+                ("Lib.Config", Some("memory"), Some("config")),
+                ("mapping_like", None, None),
+                ("bytes", Some("calldata"), None),
+            ]
+        );
+        assert!(unit.contracts[1].constructor_params.is_empty());
+        assert!(
+            parse_source_unit("contract A { constructor() {} }").unwrap().contracts[0]
+                .constructor_params
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_resolve_inheritance() {
+        let info = |name: &str, path: &str, bases: &[&str]| ContractInfo {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            import_path: path.to_string(),
+            kind: if name.starts_with('I') {
+                ContractKind::Interface
+            } else {
+                ContractKind::Contract
+            },
+            bases: bases
+                .iter()
+                .map(|b| BaseContract { name: b.to_string(), arguments: None, resolved_path: None })
+                .collect(),
+            constructor_params: Vec::new(),
+            pragma: None,
+            natspec: NatSpec::default(),
+        };
+        let mut contracts = vec![
+            info("Module", "src/Module.sol", &["Ownable"]),
+            info("IVault", "src/IVault.sol", &[]),
+            info("Vault", "src/Vault.sol", &["IVault", "Module"]),
+            info("Cycle", "src/Cycle.sol", &["Cycle"]),
+        ];
+        resolve_bases(&mut contracts);
+
+        let vault = &contracts[2];
+        let resolved: Vec<_> = vault.resolved_bases(&contracts).map(|c| c.name.as_str()).collect();
+        assert_eq!(resolved, ["IVault", "Module"]);
+        assert!(vault.inherits_from("Module", &contracts));
+        assert!(vault.inherits_from("Ownable", &contracts));
+        assert!(!contracts[0].inherits_from("IVault", &contracts));
+        assert_eq!(vault.root_base(&contracts).map(|c| c.name.as_str()), Some("Module"));
+        assert!(contracts[0].root_base(&contracts).is_none());
+        assert_eq!(contracts[3].root_base(&contracts).map(|c| c.name.as_str()), Some("Cycle"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            parse_source_unit("contract A {"),
+            Err(ParseError::UnexpectedEof { expected: "`}`" })
+        ));
+        assert!(matches!(
+            parse_source_unit("contract A { ) }"),
+            Err(ParseError::UnbalancedDelimiter { delimiter: ')', line: 1 })
+        ));
+        assert!(matches!(
+            parse_source_unit("contract {}"),
+            Err(ParseError::UnexpectedToken { expected: "contract name", .. })
+        ));
+    }
 }
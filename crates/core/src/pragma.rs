@@ -0,0 +1,312 @@
+//! Solidity version pragmas
+//!
+//! A requirement such as `^0.8.20` or `>=0.8.0 <0.9.0 || 0.7.6` is normalized into a union of
+//! half-open version ranges, so requirements can be intersected and printed back in their
+//! tightest form.
+
+use crate::errors::GeneratorError;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// A compiler version, e.g. `0.8.24`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SolidityVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl SolidityVersion {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// The smallest version greater than this one.
+    const fn next_patch(self) -> Self {
+        Self::new(self.major, self.minor, self.patch + 1)
+    }
+
+    /// The upper bound (exclusive) of `^self`.
+    const fn caret_bound(self) -> Self {
+        if self.major > 0 {
+            Self::new(self.major + 1, 0, 0)
+        } else if self.minor > 0 {
+            Self::new(0, self.minor + 1, 0)
+        } else {
+            self.next_patch()
+        }
+    }
+}
+
+impl fmt::Display for SolidityVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The range `[min, max)` of versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct VersionRange {
+    min: SolidityVersion,
+    /// The exclusive upper bound, `None` if unbounded.
+    max: Option<SolidityVersion>,
+}
+
+impl VersionRange {
+    const ANY: Self = Self { min: SolidityVersion::new(0, 0, 0), max: None };
+
+    fn is_empty(&self) -> bool {
+        self.max.is_some_and(|max| max <= self.min)
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        let max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Self { min: self.min.max(other.min), max }
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            None if self.min == SolidityVersion::new(0, 0, 0) => f.write_str("*"),
+            None => write!(f, ">={}", self.min),
+            Some(max) if max == self.min.next_patch() => write!(f, "{}", self.min),
+            Some(max) if max == self.min.caret_bound() => write!(f, "^{}", self.min),
+            Some(max) if self.min == SolidityVersion::new(0, 0, 0) => write!(f, "<{max}"),
+            Some(max) => write!(f, ">={} <{max}", self.min),
+        }
+    }
+}
+
+/// A version requirement, as written after `pragma solidity`.
+///
+/// Displays in normalized form, so `>=0.8.0 <0.9.0` is printed as `^0.8.0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct VersionReq {
+    /// Disjoint, non-empty ranges sorted by their lower bound.
+    ranges: Vec<VersionRange>,
+}
+
+impl VersionReq {
+    /// The requirement satisfied by every version.
+    pub fn any() -> Self {
+        Self { ranges: vec![VersionRange::ANY] }
+    }
+
+    /// Whether no version satisfies the requirement.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Whether `version` satisfies the requirement.
+    pub fn matches(&self, version: &SolidityVersion) -> bool {
+        self.ranges.iter().any(|r| r.min <= *version && r.max.is_none_or(|max| *version < max))
+    }
+
+    /// The requirement satisfied by the versions satisfying both `self` and `other`.
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self::from_ranges(
+            self.ranges.iter().flat_map(|a| other.ranges.iter().map(|b| a.intersect(b))).collect(),
+        )
+    }
+
+    /// Sort and merge ranges, dropping empty ones.
+    fn from_ranges(mut ranges: Vec<VersionRange>) -> Self {
+        ranges.retain(|r| !r.is_empty());
+        ranges.sort_by_key(|r| r.min);
+
+        let mut merged: Vec<VersionRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if last.max.is_none_or(|max| range.min <= max) => {
+                    last.max = match (last.max, range.max) {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        _ => None,
+                    };
+                }
+                _ => merged.push(range),
+            }
+        }
+        Self { ranges: merged }
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ranges.is_empty() {
+            return f.write_str("<0.0.0");
+        }
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" || ")?;
+            }
+            write!(f, "{range}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<VersionReq> for String {
+    fn from(req: VersionReq) -> Self {
+        req.to_string()
+    }
+}
+
+impl TryFrom<String> for VersionReq {
+    type Error = GeneratorError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GeneratorError::InvalidSolidityVersion(s.to_string());
+
+        let mut ranges = Vec::new();
+        for alternative in s.split("||") {
+            let mut range = VersionRange::ANY;
+            let mut parts = alternative.split_whitespace().peekable();
+            if parts.peek().is_none() {
+                return Err(invalid());
+            }
+            while let Some(part) = parts.next() {
+                let constraint = if part.chars().all(is_operator) {
+                    // operator separated from its version, e.g. `>= 0.8.0`
+                    let version = parts.next().ok_or_else(invalid)?;
+                    parse_comparator(&format!("{part}{version}"))
+                } else if parts.peek() == Some(&"-") {
+                    // hyphen range, e.g. `0.8.0 - 0.8.19`
+                    parts.next();
+                    let upper = parts.next().ok_or_else(invalid)?;
+                    parse_partial(part)
+                        .zip(parse_partial(upper))
+                        .map(|((min, _), (_, max))| VersionRange { min, max })
+                } else {
+                    parse_comparator(part)
+                };
+                range = range.intersect(&constraint.ok_or_else(invalid)?);
+            }
+            ranges.push(range);
+        }
+        Ok(Self::from_ranges(ranges))
+    }
+}
+
+fn is_operator(c: char) -> bool {
+    matches!(c, '^' | '~' | '<' | '>' | '=')
+}
+
+/// Parse a single comparator such as `^0.8.0`, `>=0.8` or `0.8.24`.
+fn parse_comparator(s: &str) -> Option<VersionRange> {
+    let (operator, version) = s.split_at(s.find(|c| !is_operator(c))?);
+    // `lower` is the first version matching the (possibly partial) version, `upper` the first
+    // version after all of them
+    let (lower, upper) = parse_partial(version)?;
+    let zero = SolidityVersion::new(0, 0, 0);
+
+    Some(match operator {
+        "" | "=" => VersionRange { min: lower, max: upper },
+        "^" => VersionRange { min: lower, max: upper.map(|u| u.max(lower.caret_bound())) },
+        "~" => {
+            // patch updates only, unless only a major version is given
+            let bound = if version.contains('.') {
+                SolidityVersion::new(lower.major, lower.minor + 1, 0)
+            } else {
+                SolidityVersion::new(lower.major + 1, 0, 0)
+            };
+            VersionRange { min: lower, max: upper.map(|u| u.max(bound)) }
+        }
+        ">=" => VersionRange { min: lower, max: None },
+        ">" => VersionRange { min: upper?, max: None },
+        "<" => VersionRange { min: zero, max: Some(lower) },
+        "<=" => VersionRange { min: zero, max: upper },
+        _ => return None,
+    })
+}
+
+/// Parse a version that may omit components or use wildcards.
+///
+/// Returns the first matching version and the first version after all matching ones, if any.
+fn parse_partial(s: &str) -> Option<(SolidityVersion, Option<SolidityVersion>)> {
+    let mut components = Vec::new();
+    for part in s.split('.') {
+        if matches!(part, "*" | "x" | "X") {
+            break;
+        }
+        components.push(part.parse::<u64>().ok()?);
+    }
+
+    Some(match components[..] {
+        [] => (SolidityVersion::new(0, 0, 0), None),
+        [major] => (SolidityVersion::new(major, 0, 0), Some(SolidityVersion::new(major + 1, 0, 0))),
+        [major, minor] => {
+            (SolidityVersion::new(major, minor, 0), Some(SolidityVersion::new(major, minor + 1, 0)))
+        }
+        [major, minor, patch] => {
+            let version = SolidityVersion::new(major, minor, patch);
+            (version, Some(version.next_patch()))
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(s: &str) -> VersionReq {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_normalize() {
+        assert_eq!(req("^0.8.0").to_string(), "^0.8.0");
+        assert_eq!(req(">=0.8.0 <0.9.0").to_string(), "^0.8.0");
+        assert_eq!(req("~0.8.4").to_string(), "^0.8.4");
+        assert_eq!(req("=0.8.24").to_string(), "0.8.24");
+        assert_eq!(req("0.8").to_string(), "^0.8.0");
+        assert_eq!(req(">0.8.19").to_string(), ">=0.8.20");
+        assert_eq!(req("<=0.8.19").to_string(), "<0.8.20");
+        assert_eq!(req("0.8.0 - 0.8.19").to_string(), ">=0.8.0 <0.8.20");
+        assert_eq!(req("^0.7.6 || ^0.8.0").to_string(), ">=0.7.6 <0.9.0");
+        assert_eq!(req("0.6.12 || ^0.8.0").to_string(), "0.6.12 || ^0.8.0");
+        assert_eq!(req(">=0.4.22").to_string(), ">=0.4.22");
+        assert_eq!(req("*").to_string(), "*");
+        assert_eq!(req(">= 0.8.0 < 0.9.0").to_string(), "^0.8.0");
+        assert_eq!(req("0.8.x").to_string(), "^0.8.0");
+        assert!(req(">=0.8.20 <0.8.0").is_empty());
+
+        assert!("".parse::<VersionReq>().is_err());
+        assert!("latest".parse::<VersionReq>().is_err());
+        assert!("^0.8.0 ||".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let req = req("^0.8.20");
+        assert!(req.matches(&SolidityVersion::new(0, 8, 20)));
+        assert!(req.matches(&SolidityVersion::new(0, 8, 28)));
+        assert!(!req.matches(&SolidityVersion::new(0, 8, 19)));
+        assert!(!req.matches(&SolidityVersion::new(0, 9, 0)));
+    }
+
+    #[test]
+    fn test_intersect() {
+        assert_eq!(req("^0.8.0").intersect(&req(">=0.8.20")).to_string(), "^0.8.20");
+        assert_eq!(req("^0.8.0").intersect(&req("<0.8.20")).to_string(), ">=0.8.0 <0.8.20");
+        assert_eq!(
+            req("0.6.12 || ^0.8.0").intersect(&req(">=0.6.0 <0.8.10")).to_string(),
+            "0.6.12 || >=0.8.0 <0.8.10"
+        );
+        assert_eq!(req("^0.8.0").intersect(&VersionReq::any()), req("^0.8.0"));
+        assert!(req("^0.7.0").intersect(&req("^0.8.0")).is_empty());
+    }
+}
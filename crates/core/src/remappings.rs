@@ -1,6 +1,6 @@
 //! Remappings management for Solidity imports
 
-use crate::Result;
+use crate::{Result, config::Paths};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
@@ -23,6 +23,16 @@ impl Remappings {
         Self::parse(&content)
     }
 
+    /// Load the remappings of a project: `remappings` in foundry.toml, extended by remappings.txt
+    pub fn load(paths: &Paths) -> Result<Self> {
+        let mut remappings = Self::parse(&paths.config_remappings.join("\n"))?;
+        if paths.remappings.exists() {
+            // remappings.txt takes precedence over foundry.toml, like in Foundry
+            remappings.mappings.extend(Self::from_file(&paths.remappings)?.mappings);
+        }
+        Ok(remappings)
+    }
+
     /// Parse remappings from a string
     pub fn parse(content: &str) -> Result<Self> {
         let mut mappings = HashMap::new();
@@ -66,6 +76,22 @@ impl Remappings {
         }
     }
 
+    /// Express a path relative to the project root through the remapping targeting it, e.g.
+    /// `lib/openzeppelin-contracts/contracts/access/Ownable.sol` as
+    /// `@openzeppelin/contracts/access/Ownable.sol`
+    ///
+    /// Returns `None` if no remapping targets the path.
+    pub fn unmap(&self, path: &str) -> Option<String> {
+        self.mappings
+            .iter()
+            .filter(|(_, to)| !to.is_empty() && path.starts_with(to.as_str()))
+            // the most specific target wins, ties go to the first prefix alphabetically
+            .max_by(|(a_from, a_to), (b_from, b_to)| {
+                a_to.len().cmp(&b_to.len()).then_with(|| b_from.cmp(a_from))
+            })
+            .map(|(from, to)| format!("{}{}", from, &path[to.len()..]))
+    }
+
     /// Apply remappings to file content (replace import statements)
     pub fn process_imports(&self, content: &str) -> String {
         let mut result = String::new();
@@ -126,6 +152,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unmap() {
+        let mut remappings = Remappings::new();
+        remappings.add("@openzeppelin/", "lib/openzeppelin-contracts/");
+        remappings.add("@openzeppelin/contracts/", "lib/openzeppelin-contracts/contracts/");
+        remappings.add("oz/", "lib/openzeppelin-contracts/");
+
+        assert_eq!(
+            remappings.unmap("lib/openzeppelin-contracts/contracts/access/Ownable.sol").as_deref(),
+            Some("@openzeppelin/contracts/access/Ownable.sol")
+        );
+        assert_eq!(
+            remappings.unmap("lib/openzeppelin-contracts/package.json").as_deref(),
+            Some("@openzeppelin/package.json")
+        );
+        assert_eq!(remappings.unmap("src/Vault.sol"), None);
+    }
+
     #[test]
     fn test_process_imports() {
         let mut remappings = Remappings::new();
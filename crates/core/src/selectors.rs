@@ -0,0 +1,365 @@
+//! Selectors, event topics and error selectors from ABIs
+//!
+//! Generates a library of constants from the ABIs `reflections collect` gathers: the `bytes4`
+//! selector of every function and custom error, the `bytes32` topic of every event, and the
+//! ERC-165 interface id of every interface. Signatures are canonicalized like the compiler does,
+//! with tuples expanded into their components.
+
+use crate::create2::{encode_hex, keccak256};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// What an ABI entry declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AbiEntryKind {
+    Function,
+    Event,
+    Error,
+}
+
+/// A function, event or custom error of an ABI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbiEntry {
+    pub kind: AbiEntryKind,
+    pub name: String,
+    /// The canonical parameter types, e.g. `["address", "(uint256,bytes)[]"]`.
+    pub types: Vec<String>,
+}
+
+impl AbiEntry {
+    /// The canonical signature, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.types.join(","))
+    }
+
+    /// The Keccak-256 hash of the signature: the topic of an event, whose first four bytes are
+    /// the selector of a function or error.
+    pub fn hash(&self) -> [u8; 32] {
+        keccak256(self.signature().as_bytes())
+    }
+
+    /// The first four bytes of the hash.
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = self.hash();
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+}
+
+/// The functions, events and custom errors of an ABI, in ABI order. Anonymous events have no
+/// topic and are skipped, as are entries that are not well-formed, and an entry inherited through
+/// several parents is only kept once.
+pub fn abi_entries(abi: &Value) -> Vec<AbiEntry> {
+    let mut seen = HashSet::new();
+    abi.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let kind = match entry.get("type")?.as_str()? {
+                "function" => AbiEntryKind::Function,
+                "event" if entry.get("anonymous").and_then(Value::as_bool) != Some(true) => {
+                    AbiEntryKind::Event
+                }
+                "error" => AbiEntryKind::Error,
+                _ => return None,
+            };
+            let types = entry
+                .get("inputs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(canonical_type)
+                .collect::<Option<Vec<_>>>()?;
+            Some(AbiEntry { kind, name: entry.get("name")?.as_str()?.to_string(), types })
+        })
+        .filter(|entry| seen.insert((entry.kind, entry.signature())))
+        .collect()
+}
+
+/// The canonical type of an ABI parameter, with tuples written as their components.
+fn canonical_type(param: &Value) -> Option<String> {
+    let ty = param.get("type")?.as_str()?;
+    let Some(dimensions) = ty.strip_prefix("tuple") else {
+        return Some(ty.to_string());
+    };
+    let components = param
+        .get("components")?
+        .as_array()?
+        .iter()
+        .map(canonical_type)
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({}){dimensions}", components.join(",")))
+}
+
+/// The ERC-165 interface id of an interface: the XOR of the selectors of its functions, without
+/// the functions it inherits, whose ABIs are `inherited`.
+pub fn interface_id(abi: &Value, inherited: &[&Value]) -> [u8; 4] {
+    let inherited: HashSet<[u8; 4]> = inherited
+        .iter()
+        .flat_map(|abi| abi_entries(abi))
+        .filter(|e| e.kind == AbiEntryKind::Function)
+        .map(|e| e.selector())
+        .collect();
+    abi_entries(abi)
+        .iter()
+        .filter(|e| e.kind == AbiEntryKind::Function)
+        .map(AbiEntry::selector)
+        .filter(|selector| !inherited.contains(selector))
+        .fold([0; 4], |id, selector| std::array::from_fn(|i| id[i] ^ selector[i]))
+}
+
+/// A contract to generate constants for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectorsContract {
+    pub name: String,
+    pub abi: Value,
+    /// The ERC-165 interface id, for interfaces.
+    pub interface_id: Option<[u8; 4]>,
+}
+
+pub struct SelectorsOptions {
+    pub library_name: String,
+    pub license: String,
+    pub solidity_version: String,
+}
+
+impl Default for SelectorsOptions {
+    fn default() -> Self {
+        Self {
+            library_name: "Selectors".to_string(),
+            license: "MIT".to_string(),
+            solidity_version: "^0.8.0".to_string(),
+        }
+    }
+}
+
+/// Generate the constants library, one block per contract in the given order.
+///
+/// Functions are named `<Contract>_<function>`, events `<Contract>_<Event>_TOPIC`, errors
+/// `<Contract>_<Error>_ERROR` and interface ids `<Contract>_INTERFACE_ID`. Overloads get their
+/// parameter types appended, e.g. `Token_transfer_address_uint256`.
+pub fn generate_selectors_library(
+    contracts: &[SelectorsContract],
+    options: &SelectorsOptions,
+) -> String {
+    let blocks: Vec<String> = contracts
+        .iter()
+        .filter_map(|contract| {
+            let prefix = sanitize_identifier(&contract.name);
+            let entries = abi_entries(&contract.abi);
+            let base_name = |entry: &AbiEntry| {
+                let suffix = match entry.kind {
+                    AbiEntryKind::Function => "",
+                    AbiEntryKind::Event => "_TOPIC",
+                    AbiEntryKind::Error => "_ERROR",
+                };
+                format!("{prefix}_{}{suffix}", entry.name)
+            };
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for entry in &entries {
+                *counts.entry(base_name(entry)).or_default() += 1;
+            }
+
+            let mut lines: Vec<String> = entries
+                .iter()
+                .map(|entry| {
+                    let mut name = base_name(entry);
+                    if counts[&name] > 1 {
+                        let (stem, suffix) = match entry.kind {
+                            AbiEntryKind::Function => (name.as_str(), ""),
+                            AbiEntryKind::Event => (name.trim_end_matches("_TOPIC"), "_TOPIC"),
+                            AbiEntryKind::Error => (name.trim_end_matches("_ERROR"), "_ERROR"),
+                        };
+                        let types: String = entry
+                            .types
+                            .iter()
+                            .map(|ty| format!("_{}", type_identifier(ty)))
+                            .collect();
+                        name = sanitize_identifier(&format!("{stem}{types}{suffix}"));
+                    }
+                    match entry.kind {
+                        AbiEntryKind::Event => format!(
+                            "    bytes32 internal constant {name} = 0x{}; // event {}",
+                            encode_hex(&entry.hash()),
+                            entry.signature()
+                        ),
+                        AbiEntryKind::Error => format!(
+                            "    bytes4 internal constant {name} = 0x{}; // error {}",
+                            encode_hex(&entry.selector()),
+                            entry.signature()
+                        ),
+                        AbiEntryKind::Function => format!(
+                            "    bytes4 internal constant {name} = 0x{}; // {}",
+                            encode_hex(&entry.selector()),
+                            entry.signature()
+                        ),
+                    }
+                })
+                .collect();
+            if let Some(id) = contract.interface_id {
+                lines.push(format!(
+                    "    bytes4 internal constant {prefix}_INTERFACE_ID = 0x{};",
+                    encode_hex(&id)
+                ));
+            }
+            if lines.is_empty() {
+                return None;
+            }
+            Some(format!("    // {}\n{}", contract.name, lines.join("\n")))
+        })
+        .collect();
+
+    format!(
+        r#"// SPDX-License-Identifier: {}
+pragma solidity {};
+
+/// @title {}
+/// @notice Auto-generated function selectors, event topics, error selectors and interface ids
+/// @dev Generated by solidity-reflections
+library {} {{
+{}
+}}
+"#,
+        options.license,
+        options.solidity_version,
+        options.library_name,
+        options.library_name,
+        blocks.join("\n\n")
+    )
+}
+
+/// A parameter type as part of an identifier, e.g. `uint256Array` for `uint256[]`.
+fn type_identifier(ty: &str) -> String {
+    ty.replace("[]", "Array").replace('[', "Array").replace(']', "")
+}
+
+fn sanitize_identifier(ident: &str) -> String {
+    ident.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn erc20() -> Value {
+        let function = |name: &str, inputs: &[&str]| {
+            json!({
+                "type": "function",
+                "name": name,
+                "inputs": inputs.iter().map(|ty| json!({ "name": "", "type": ty })).collect::<Vec<_>>(),
+            })
+        };
+        json!([
+            function("totalSupply", &[]),
+            function("balanceOf", &["address"]),
+            function("transfer", &["address", "uint256"]),
+            function("allowance", &["address", "address"]),
+            function("approve", &["address", "uint256"]),
+            function("transferFrom", &["address", "address", "uint256"]),
+            {
+                "type": "event",
+                "name": "Transfer",
+                "anonymous": false,
+                "inputs": [
+                    { "name": "from", "type": "address", "indexed": true },
+                    { "name": "to", "type": "address", "indexed": true },
+                    { "name": "value", "type": "uint256", "indexed": false }
+                ]
+            }
+        ])
+    }
+
+    #[test]
+    fn test_selectors() {
+        let entries = abi_entries(&erc20());
+        assert_eq!(entries[2].signature(), "transfer(address,uint256)");
+        assert_eq!(encode_hex(&entries[2].selector()), "a9059cbb");
+        assert_eq!(
+            encode_hex(&entries[6].hash()),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+        assert_eq!(encode_hex(&interface_id(&erc20(), &[])), "36372b07");
+
+        // IERC20Metadata is IERC20
+        let mut metadata = erc20();
+        for name in ["name", "symbol", "decimals"] {
+            metadata
+                .as_array_mut()
+                .unwrap()
+                .push(json!({ "type": "function", "name": name, "inputs": [] }));
+        }
+        assert_eq!(encode_hex(&interface_id(&metadata, &[&erc20()])), "a219a025");
+    }
+
+    #[test]
+    fn test_canonical_tuples() {
+        let abi = json!([{
+            "type": "error",
+            "name": "Invalid",
+            "inputs": [{
+                "name": "orders",
+                "type": "tuple[]",
+                "components": [
+                    { "name": "maker", "type": "address" },
+                    { "name": "amounts", "type": "uint256[2]" },
+                    { "name": "fee", "type": "tuple", "components": [{ "name": "bps", "type": "uint16" }] }
+                ]
+            }]
+        }]);
+        let entries = abi_entries(&abi);
+        assert_eq!(entries[0].signature(), "Invalid((address,uint256[2],(uint16))[])");
+    }
+
+    #[test]
+    fn test_generate_selectors_library() {
+        let mut abi = erc20();
+        abi.as_array_mut().unwrap().push(json!({
+            "type": "function",
+            "name": "transfer",
+            "inputs": [{ "name": "to", "type": "address" }, { "name": "ids", "type": "uint256[]" }]
+        }));
+        let contracts = [
+            SelectorsContract { name: "Token".into(), abi, interface_id: None },
+            SelectorsContract {
+                name: "IERC20".into(),
+                abi: erc20(),
+                interface_id: Some([0x36, 0x37, 0x2b, 0x07]),
+            },
+            SelectorsContract { name: "Empty".into(), abi: json!([]), interface_id: None },
+        ];
+        let generated = generate_selectors_library(&contracts, &SelectorsOptions::default());
+        assert!(generated.contains("library Selectors {\n    // Token\n"));
+        assert!(generated.contains(
+            "    bytes4 internal constant Token_balanceOf = 0x70a08231; // balanceOf(address)"
+        ));
+        // overloads are told apart by their parameter types
+        assert!(
+            generated
+                .contains("bytes4 internal constant Token_transfer_address_uint256 = 0xa9059cbb;")
+        );
+        assert!(
+            generated.contains("bytes4 internal constant Token_transfer_address_uint256Array = 0x")
+        );
+        assert!(
+            generated.contains("    bytes32 internal constant IERC20_Transfer_TOPIC = 0xddf252ad")
+        );
+        assert!(
+            generated
+                .contains("    bytes4 internal constant IERC20_INTERFACE_ID = 0x36372b07;\n}\n")
+        );
+        assert!(!generated.contains("Empty"));
+    }
+
+    #[test]
+    fn test_inherited_twice() {
+        // an error declared by two parents of the contract
+        let mut abi = erc20();
+        let error = json!({ "type": "error", "name": "Unauthorized", "inputs": [] });
+        abi.as_array_mut().unwrap().extend([error.clone(), error]);
+        assert_eq!(abi_entries(&abi).len(), 8);
+
+        let contracts = [SelectorsContract { name: "Token".into(), abi, interface_id: None }];
+        let generated = generate_selectors_library(&contracts, &SelectorsOptions::default());
+        assert_eq!(generated.matches("Token_Unauthorized_ERROR = 0x").count(), 1);
+    }
+}
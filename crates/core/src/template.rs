@@ -0,0 +1,212 @@
+//! Templates for the generated reflection library
+//!
+//! The library is rendered from a [MiniJinja](https://docs.rs/minijinja) template. Projects can
+//! replace the built-in one by putting a `library.sol.j2` into their template directory, e.g. to
+//! add their own helper functions. The dispatcher of sharded libraries is rendered from
+//! `dispatcher.sol.j2` the same way. Templates get a typed [`TemplateContext`]: the options, the
+//! paths, every contract with its kind, and the sections Reflections generates itself.
+
+use crate::errors::GeneratorError;
+use minijinja::{Environment, UndefinedBehavior};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The default template directory, relative to the project root.
+pub const TEMPLATE_DIR: &str = ".reflections/templates";
+
+/// The file name of the library template inside the template directory.
+pub const LIBRARY_TEMPLATE: &str = "library.sol.j2";
+
+/// The file name of the dispatcher template inside the template directory.
+pub const DISPATCHER_TEMPLATE: &str = "dispatcher.sol.j2";
+
+/// The built-in library template.
+pub const DEFAULT_LIBRARY_TEMPLATE: &str = include_str!("../templates/library.sol.j2");
+
+/// The built-in dispatcher template.
+pub const DEFAULT_DISPATCHER_TEMPLATE: &str = include_str!("../templates/dispatcher.sol.j2");
+
+/// A library template and the file it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryTemplate {
+    /// The template file, `None` for the built-in template.
+    pub path: Option<String>,
+    pub source: String,
+}
+
+impl Default for LibraryTemplate {
+    fn default() -> Self {
+        Self { path: None, source: DEFAULT_LIBRARY_TEMPLATE.to_string() }
+    }
+}
+
+impl LibraryTemplate {
+    /// The built-in dispatcher template.
+    pub fn dispatcher() -> Self {
+        Self { path: None, source: DEFAULT_DISPATCHER_TEMPLATE.to_string() }
+    }
+
+    /// Load the library template of `dir`, or the built-in one if `dir` has none.
+    pub fn load(dir: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::load_or(dir.as_ref().join(LIBRARY_TEMPLATE), Self::default())
+    }
+
+    /// Load the dispatcher template of `dir`, or the built-in one if `dir` has none.
+    pub fn load_dispatcher(dir: impl AsRef<Path>) -> crate::Result<Self> {
+        Self::load_or(dir.as_ref().join(DISPATCHER_TEMPLATE), Self::dispatcher())
+    }
+
+    fn load_or(path: PathBuf, builtin: Self) -> crate::Result<Self> {
+        if !path.is_file() {
+            return Ok(builtin);
+        }
+        Ok(Self { path: Some(path.display().to_string()), source: fs::read_to_string(&path)? })
+    }
+
+    /// Whether the template derives the salts from `SALT_NAMESPACE`, itself or through the
+    /// generated `toSalt`.
+    pub fn uses_salt_namespace(&self) -> bool {
+        self.source.contains("SALT_NAMESPACE") || self.source.contains("sections.to_salt")
+    }
+
+    /// Render the template. Undefined context variables are errors, so typos do not silently
+    /// produce an empty string.
+    pub fn render(&self, context: &TemplateContext) -> Result<String, GeneratorError> {
+        let name = self.path.as_deref().unwrap_or(LIBRARY_TEMPLATE);
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        env.add_template(name, &self.source)
+            .and_then(|_| env.get_template(name)?.render(context))
+            .map_err(|e| GeneratorError::Template(format!("{name}: {e:#}")))
+    }
+}
+
+/// Everything a library template can use.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateContext {
+    pub options: TemplateOptions,
+    pub paths: TemplatePaths,
+    /// The contracts with a `Source` variant, sorted by name.
+    pub contracts: Vec<TemplateContract>,
+    /// The shard libraries, only for the dispatcher.
+    pub shards: Vec<TemplateShard>,
+    pub sections: TemplateSections,
+}
+
+/// The generator options.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateOptions {
+    pub library_name: String,
+    pub license: String,
+    pub solidity_version: String,
+    /// `all` or `deployable`.
+    pub enum_policy: String,
+    pub disambiguate: bool,
+    pub from_string: bool,
+    pub enumeration: bool,
+    pub is_deployable: bool,
+    /// Whether `initCodeHash` and `predictAddress` are generated.
+    pub init_code_hashes: bool,
+    /// `<project>@<version>`, naming the salt namespace `SALT_NAMESPACE` is derived from. Empty
+    /// without one.
+    pub salt_namespace: String,
+    /// Whether the salts include `block.chainid`.
+    pub salt_chain_id: bool,
+}
+
+/// Where the library is generated from and to, relative to the project root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TemplatePaths {
+    pub contracts_dir: String,
+    pub output: String,
+}
+
+/// A contract with a `Source` variant.
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateContract {
+    /// The identifier of the variant and of its `Source` constant, e.g. `Vault`, or `Vault_v2`
+    /// when disambiguated.
+    pub ident: String,
+    /// The name the contract is imported as, e.g. `_Vault`, since the `Source` constant takes
+    /// `ident`.
+    pub alias: String,
+    /// The name the contract is declared with.
+    pub name: String,
+    pub import_path: String,
+    /// `contract`, `abstract contract`, `interface` or `library`.
+    pub kind: String,
+    /// Whether the contract has creation code.
+    pub deployable: bool,
+    /// The constructor parameter types.
+    pub constructor_params: Vec<String>,
+}
+
+/// A shard library of a sharded `Source` enum.
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateShard {
+    /// The id in the upper bits of the dispatcher `Source` values.
+    pub id: usize,
+    /// The name of the shard library, e.g. `SourcesTokens`.
+    pub library_name: String,
+    /// The identifiers of the contract variants of the shard.
+    pub contracts: Vec<String>,
+}
+
+/// The sections of the built-in templates, rendered by Reflections.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TemplateSections {
+    /// The import of every contract, one per line.
+    pub imports: String,
+    /// The contract variants of the `Source` enum, after `NONE` and
+    /// `TransparentUpgradeableProxy`. For the dispatcher, the conversions from the shard enums.
+    pub enum_variants: String,
+    /// The `Source` constant of every contract, e.g. `Sources.Vault`.
+    pub constants: String,
+    /// `SALT_NAMESPACE` if there is a salt namespace, and the `toSalt` overloads.
+    pub to_salt: String,
+    /// `toCreationCode(Source)`.
+    pub to_creation_code: String,
+    /// The `toCreationCode` overloads appending constructor arguments.
+    pub to_creation_code_with_args: String,
+    /// `toString(Source)`.
+    pub to_string: String,
+    /// `fromString(string)`, empty if disabled.
+    pub from_string: String,
+    /// `all()` and `count()`, empty if disabled.
+    pub enumeration: String,
+    /// `isDeployable(Source)`, empty if disabled.
+    pub is_deployable: String,
+    /// `initCodeHash(Source)` and the `predictAddress` overloads, empty if disabled.
+    pub init_code_hash: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_errors() {
+        let template =
+            LibraryTemplate { path: None, source: "library {{ options.library_nam }} {}".into() };
+        let err = template.render(&TemplateContext::default()).unwrap_err();
+        assert!(matches!(err, GeneratorError::Template(_)), "{err}");
+
+        let template = LibraryTemplate { path: None, source: "{% if %}".into() };
+        assert!(template.render(&TemplateContext::default()).is_err());
+    }
+
+    #[test]
+    fn test_uses_salt_namespace() {
+        assert!(LibraryTemplate::default().uses_salt_namespace());
+        assert!(LibraryTemplate::dispatcher().uses_salt_namespace());
+        let template = LibraryTemplate {
+            path: None,
+            source: "function toSalt() { return keccak256(\"x\"); }".into(),
+        };
+        assert!(!template.uses_salt_namespace());
+    }
+}
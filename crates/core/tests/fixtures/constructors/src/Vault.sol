@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IToken {
+    function balanceOf(address account) external view returns (uint256);
+}
+
+contract Vault {
+    struct Config {
+        uint256 cap;
+    }
+
+    address public owner;
+    IToken public token;
+
+    constructor(address owner_, uint256[] memory caps, IToken token_, string memory label) {
+        owner = owner_;
+        token = token_;
+        caps;
+        label;
+    }
+}
+
+contract Escrow {
+    address public owner;
+
+    constructor(address payable owner_) {
+        owner = owner_;
+    }
+}
+
+contract Treasury {
+    address public owner;
+
+    constructor(address owner_) payable {
+        owner = owner_;
+    }
+}
+
+contract Configured {
+    constructor(Vault.Config memory config) {
+        config;
+    }
+}
+
+contract Blob {
+    constructor(bytes memory data) {
+        data;
+    }
+}
+
+contract NoArgs {
+    constructor() {}
+}
@@ -0,0 +1,8 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+contract Vault {
+    uint256 public constant VERSION = 2;
+}
+
+contract Router {}
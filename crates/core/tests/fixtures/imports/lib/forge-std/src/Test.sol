@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: MIT
+pragma solidity >=0.6.2 <0.9.0;
+
+import { Vm } from "./Vm.sol";
+
+abstract contract Test {}
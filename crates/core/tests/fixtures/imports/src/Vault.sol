@@ -0,0 +1,10 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import { Owned } from "./access/Owned.sol";
+import { FixedPoint } from "@math/FixedPoint.sol";
+import { Test } from "forge-std/Test.sol";
+import "src/access/Owned.sol";
+import { Oracle } from "@missing/Oracle.sol";
+
+contract Vault is Owned {}
@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import { Errors } from "../utils/Errors.sol";
+
+abstract contract Owned {}
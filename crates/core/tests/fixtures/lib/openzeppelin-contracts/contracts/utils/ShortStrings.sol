@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+// Minimal stand-in for OpenZeppelin's ShortStrings, enough to compile generated libraries.
+
+type ShortString is bytes32;
+
+library ShortStrings {
+    function toShortString(string memory str) internal pure returns (ShortString) {
+        return ShortString.wrap(bytes32(bytes(str)));
+    }
+
+    function toString(ShortString sstr) internal pure returns (string memory) {
+        return string(abi.encodePacked(ShortString.unwrap(sstr)));
+    }
+}
@@ -0,0 +1,12 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+abstract contract Base {
+    function version() public pure virtual returns (string memory);
+}
+
+contract Impl is Base {
+    function version() public pure override returns (string memory) {
+        return "contract Fake {}";
+    }
+}
@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IToken {
+    function balanceOf(address account) external view returns (uint256);
+}
+
+/// @notice A token whose doc comment mentions contract Phantom {}
+contract Token is IToken {
+    mapping(address => uint256) private balances;
+
+    function balanceOf(address account) external view returns (uint256) {
+        return balances[account];
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+type Shares is uint256;
+
+struct Position {
+    address owner;
+    mapping(address => uint256) allowances;
+}
+
+error Unauthorized(address caller);
+
+event Paused(address indexed by) anonymous;
+
+function toShares(uint256 amount) pure returns (Shares) {
+    return Shares.wrap(amount);
+}
+
+contract Vault {
+    enum Status {
+        Active,
+        Paused
+    }
+
+    struct Deposit {
+        uint256 amount;
+        uint64 at;
+    }
+
+    event Deposited(address indexed owner, uint256 amount);
+
+    error InsufficientBalance(uint256 available, uint256 required);
+
+    function(uint256) external pure returns (uint256) internal callback;
+
+    constructor(address owner) {}
+
+    modifier onlyOwner() {
+        _;
+    }
+
+    function deposit(uint256 amount) external payable returns (Shares shares) {
+        emit Deposited(msg.sender, amount);
+        return toShares(amount);
+    }
+
+    function balanceOf(address owner) public view virtual returns (uint256) {
+        return 0;
+    }
+}
@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.7.6;
+
+interface IOracle {
+    function price() external view returns (uint256);
+}
@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.12;
+
+interface IWiringMechanism {
+    enum SupportedWiring {
+        NONE,
+        PLAIN_NICKNAMED,
+        PLAIN,
+        CONFIGURATION_BASED
+    }
+
+    function wire(bytes memory wiringInfo, SupportedWiring wiringType) external returns (address);
+
+    function getWiredVariants(bytes memory wiringInfo, SupportedWiring wiringType) external view returns (address[] memory);
+}
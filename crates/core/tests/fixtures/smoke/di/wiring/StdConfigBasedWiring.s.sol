@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.12;
+
+import { Sources } from "Sources.s.sol";
+import { IWiringMechanism } from "di/interfaces/IWiringMechanism.s.sol";
+
+/// @dev Stands in for the scaffolded wiring, deploying plain sources with CREATE2 like it does.
+contract StdConfigBasedWiring is IWiringMechanism {
+    using Sources for Sources.Source;
+
+    mapping(bytes32 => address) internal wired;
+
+    function wire(bytes memory wiringInfo, SupportedWiring) external returns (address deployed) {
+        Sources.Source source = abi.decode(wiringInfo, (Sources.Source));
+        bytes memory creationCode = source.toCreationCode();
+        bytes32 salt = source.toSalt();
+        assembly {
+            deployed := create2(0, add(creationCode, 0x20), mload(creationCode), salt)
+        }
+        wired[keccak256(wiringInfo)] = deployed;
+    }
+
+    function getWiredVariants(bytes memory wiringInfo, SupportedWiring) external view returns (address[] memory result) {
+        result = new address[](1);
+        result[0] = wired[keccak256(wiringInfo)];
+    }
+}
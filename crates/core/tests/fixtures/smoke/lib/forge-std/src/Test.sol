@@ -0,0 +1,22 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.12;
+
+/// @dev The cheatcodes the generated smoke tests use.
+interface Vm {
+    function computeCreate2Address(bytes32 salt, bytes32 initCodeHash, address deployer) external pure returns (address);
+
+    function skip(bool skipTest) external;
+}
+
+/// @dev Stands in for forge-std's `Test` with the assertions the generated smoke tests use.
+abstract contract Test {
+    Vm internal constant vm = Vm(address(uint160(uint256(keccak256("hevm cheat code")))));
+
+    function assertEq(address left, address right, string memory err) internal pure {
+        require(left == right, err);
+    }
+
+    function assertGt(uint256 left, uint256 right, string memory err) internal pure {
+        require(left > right, err);
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.12;
+
+import { ShortStrings, ShortString } from "@openzeppelin/contracts/utils/ShortStrings.sol";
+
+import { Sources } from "Sources.s.sol";
+
+/// @dev What the scaffolded wiring and configurations use of the library, which the `Source` enum
+/// and the `uint256` `Source` of sharded libraries both have to provide, including the
+/// `Sources.<Contract>` constants.
+contract Wiring {
+    using Sources for Sources.Source;
+
+    function wire(bytes memory wiringInfo) external view returns (bytes32, bytes memory, string memory) {
+        Sources.Source source = abi.decode(wiringInfo, (Sources.Source));
+        return (source.toSalt(), source.toCreationCode(), source.toString());
+    }
+
+    function nickwire(bytes memory wiringInfo) external view returns (bytes32, string memory) {
+        (Sources.Source source, ShortString nickname) = abi.decode(wiringInfo, (Sources.Source, ShortString));
+        return (source.toSalt(nickname), source.getFullNicknamedName(nickname));
+    }
+
+    function proxyKey(Sources.Source implementation) external pure returns (string memory) {
+        return Sources.TransparentUpgradeableProxy.getFullNicknamedName(
+            ShortStrings.toShortString(implementation.toString())
+        );
+    }
+
+    function unset() external pure returns (Sources.Source) {
+        return Sources.NONE;
+    }
+
+    function configure(string memory name) external pure returns (bytes memory, bytes32, bytes32) {
+        return (abi.encode(Sources.fromString(name)), Sources.NICKNAMED_PROXY_FLAG, Sources.EIP4337_FLAG);
+    }
+}
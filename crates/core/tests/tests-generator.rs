@@ -0,0 +1,979 @@
+use reflections_core::{
+    ReflectionsError,
+    artifacts::ArtifactIndex,
+    cache::{DISCOVERY_CACHE_PATH, DiscoveryCache},
+    config::Paths,
+    create2::{SaltNamespace, decode_hex, encode_hex, keccak256},
+    errors::{GeneratorError, ImportError},
+    generator::{
+        AUTO_PRAGMA, EnumPolicy, GeneratedLibrary, GeneratorOptions, Sharding, diff_library,
+        generate_libraries, generate_smoke_tests, generate_sources_library, resolve_pragma,
+        source_variants, stale_init_code_hashes, variant_names, variant_shards,
+    },
+    imports::{ImportGraph, ImportResolver},
+    lockfile::SourceLock,
+    model::{Dependency, ProjectModel},
+    parser::{
+        ContractKind, DeclarationKind, DiscoveryFilter, discover_contracts,
+        discover_contracts_cached,
+    },
+    utils::copy_dir_recursive,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use testdir::testdir;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+/// Compile the generated libraries with `solc` inside a copy of the fixture project, the first
+/// one (the library or the dispatcher) as `Sources.s.sol` and the shards next to it, together with
+/// a contract using them like the scaffolded wiring does.
+///
+/// Set `SOLC` to use a specific binary.
+fn assert_compiles(project: &Path, dir: &Path, libraries: &[GeneratedLibrary], smoke_tests: &str) {
+    copy_dir_recursive(project, dir).expect("fixture should be copied");
+    copy_dir_recursive(fixture("lib"), dir.join("lib")).expect("fixture libs should be copied");
+    copy_dir_recursive(fixture("wiring"), dir).expect("wiring fixture should be copied");
+    copy_dir_recursive(fixture("smoke"), dir).expect("smoke test stubs should be copied");
+    fs::write(dir.join("Sources.t.sol"), smoke_tests).expect("smoke tests should be written");
+    fs::write(dir.join("Sources.s.sol"), &libraries[0].code)
+        .expect("generated library should be written");
+    for library in &libraries[1..] {
+        fs::write(dir.join(format!("{}.s.sol", library.library_name)), &library.code)
+            .expect("shard library should be written");
+    }
+
+    let solc = std::env::var("SOLC").unwrap_or_else(|_| "solc".to_string());
+    let output = Command::new(&solc)
+        .current_dir(dir)
+        .args([
+            "--base-path",
+            ".",
+            "@openzeppelin/contracts/=lib/openzeppelin-contracts/contracts/",
+            "forge-std/=lib/forge-std/src/",
+            "Sources.s.sol",
+            "Wiring.sol",
+            "Sources.t.sol",
+        ])
+        .output()
+        .unwrap_or_else(|e| panic!("failed to run `{solc}`, install solc or set SOLC: {e}"));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+/// Compile the libraries and smoke tests generated from a fixture project with `options`. The
+/// tests doing so need `solc`, so they are ignored and only run with `cargo test -- --ignored`,
+/// like the `solidity` CI job does.
+fn assert_fixture_compiles(name: &str, mut options: GeneratorOptions) {
+    options.paths.output = "Sources.s.sol".to_string();
+    let project = fixture(name);
+    let contracts = discover_contracts(&project, "src").expect("fixture should be discovered");
+    let libraries = generate_libraries(&contracts, &options).expect("libraries should generate");
+    let smoke_tests =
+        generate_smoke_tests(&contracts, &options, "di").expect("smoke tests should generate");
+    assert_compiles(&project, &testdir!(), &libraries, &smoke_tests);
+}
+
+/// A cheap stand-in for [`assert_compiles`] that needs no `solc`: the brackets of the libraries
+/// balance, every `Source.<Variant>` is declared in the enum of its library, every contract and
+/// shard library the code names is imported, and the imported files exist in the fixture project.
+fn assert_well_formed(project: &Path, libraries: &[&str]) {
+    let idents = |code: &str, prefix: &str| -> Vec<String> {
+        code.match_indices(prefix)
+            .filter(|(i, _)| !code[..*i].ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_'))
+            .map(|(i, _)| {
+                code[i + prefix.len()..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                    .collect()
+            })
+            .collect()
+    };
+
+    let library_names: Vec<String> =
+        libraries.iter().flat_map(|code| idents(code, "library ")).collect();
+
+    for code in libraries {
+        for (open, close) in [('{', '}'), ('(', ')'), ('[', ']')] {
+            assert_eq!(code.matches(open).count(), code.matches(close).count(), "{code}");
+        }
+
+        if let Some(lock) = SourceLock::from_library(code) {
+            let variants: Vec<_> = ["NONE", "TransparentUpgradeableProxy"]
+                .map(String::from)
+                .into_iter()
+                .chain(lock.variants.iter().map(|v| v.ident()))
+                .collect();
+            for ident in idents(code, "Source.") {
+                assert!(variants.contains(&ident), "`Source.{ident}` is not a variant:\n{code}");
+            }
+        }
+
+        let imported: Vec<&str> = code
+            .lines()
+            .filter_map(|line| line.strip_prefix("import { ")?.split_once(" }"))
+            .map(|(names, _)| names.rsplit(" as ").next().unwrap_or(names))
+            .collect();
+        for ident in idents(code, "type(") {
+            assert!(imported.contains(&ident.as_str()), "`{ident}` is not imported:\n{code}");
+        }
+        for library in &library_names {
+            if !code.contains(&format!("library {library} {{")) &&
+                !idents(code, &format!("{library}.")).is_empty()
+            {
+                assert!(imported.contains(&library.as_str()), "`{library}` is not imported");
+            }
+        }
+        for line in code.lines().filter(|line| line.starts_with("import ")) {
+            let path = line.rsplit('"').nth(1).expect("import should have a path");
+            match path.strip_prefix("./") {
+                Some(file) => {
+                    let library = file.trim_end_matches(".s.sol");
+                    let declared = format!("library {library} {{");
+                    assert!(libraries.iter().any(|l| l.contains(&declared)), "{line}");
+                }
+                None if path.starts_with('@') => {}
+                None => assert!(project.join(path).exists(), "{line}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_generator_mixed_kinds_all() {
+    let project = fixture("mixed");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let generated = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap();
+
+    for name in ["Base", "IToken", "Impl", "MathLib", "Token"] {
+        assert!(generated.contains(&format!("        {name}")), "missing variant {name}");
+    }
+    assert!(!generated.contains("Phantom"));
+    assert!(!generated.contains("Fake"));
+
+    // only deployable kinds may reference creation code
+    assert!(generated.contains("return type(_Token).creationCode;"));
+    assert!(generated.contains("return type(_Impl).creationCode;"));
+    assert!(generated.contains("return type(_MathLib).creationCode;"));
+    assert!(!generated.contains("type(_IToken).creationCode"));
+    assert!(!generated.contains("type(_Base).creationCode"));
+    assert!(generated.contains("revert NonDeployableMetaArtifact(); // interface"));
+    assert!(generated.contains("revert NonDeployableMetaArtifact(); // abstract contract"));
+
+    // sources are `Sources.<Contract>` like in the dispatcher, so the imports are aliased
+    assert!(generated.contains(r#"import { Token as _Token } from "src/tokens/Token.sol";"#));
+    assert!(generated.contains("    Source internal constant Token = Source.Token;"));
+    assert!(generated.contains(
+        "    Source internal constant TransparentUpgradeableProxy = Source.TransparentUpgradeableProxy;"
+    ));
+
+    assert_well_formed(&project, &[&generated]);
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_mixed_kinds_all_compiles() {
+    assert_fixture_compiles("mixed", GeneratorOptions::default());
+}
+
+#[test]
+fn test_generator_import_aliases() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/Vault.sol"), "contract Vault {}\n\ncontract _Vault {}\n").unwrap();
+    let contracts = discover_contracts(&dir, "src").unwrap();
+    let generated = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap();
+
+    // an alias must neither be a `Source` constant nor the alias of another contract
+    assert!(generated.contains(r#"import { Vault as __Vault } from "src/Vault.sol";"#));
+    assert!(generated.contains(r#"import { _Vault as ___Vault } from "src/Vault.sol";"#));
+    assert!(generated.contains("    Source internal constant _Vault = Source._Vault;"));
+}
+
+#[test]
+fn test_generator_mixed_kinds_deployable_only() {
+    let project = fixture("mixed");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let options = GeneratorOptions { enum_policy: EnumPolicy::Deployable, ..Default::default() };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+
+    assert!(generated.contains("type(_Token).creationCode"));
+    assert!(generated.contains("type(_Impl).creationCode"));
+    assert!(!generated.contains("IToken"));
+    assert!(!generated.contains("Source.Base"));
+    assert!(!generated.contains("revert NonDeployableMetaArtifact();"));
+
+    assert_well_formed(&project, &[&generated]);
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_mixed_kinds_deployable_only_compiles() {
+    let options = GeneratorOptions { enum_policy: EnumPolicy::Deployable, ..Default::default() };
+    assert_fixture_compiles("mixed", options);
+}
+
+#[test]
+fn test_generator_lookup_helpers() {
+    let project = fixture("mixed");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let generated = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap();
+
+    assert!(
+        generated
+            .contains("function fromString(string memory name) internal pure returns (Source)")
+    );
+    assert!(generated.contains(
+        "        if (nameHash == keccak256(\"Token\")) {\n            return Source.Token;\n        }"
+    ));
+    assert!(generated.contains("sources = new Source[](5);\n        sources[0] = Source.Base;"));
+    assert!(
+        generated.contains("function count() internal pure returns (uint256) {\n        return 5;")
+    );
+    // interfaces and abstract contracts have no creation code
+    assert!(generated.contains("if (metaArtifact == Source.Impl) {\n            return true;"));
+    assert!(!generated.contains("if (metaArtifact == Source.IToken) {\n            return true;"));
+    assert_well_formed(&project, &[&generated]);
+
+    // with a lockfile, `all()` follows the ordinals and skips tombstones
+    let mut lock = SourceLock::default();
+    let names = ["Token", "Gone", "Base", "IToken", "Impl", "MathLib"].map(String::from);
+    lock.update(&names).unwrap();
+    lock.update(&names.into_iter().filter(|n| n != "Gone").collect::<Vec<_>>()).unwrap();
+    let options = GeneratorOptions { lock: Some(lock), ..Default::default() };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+    assert!(
+        generated.contains("        sources[0] = Source.Token;\n        sources[1] = Source.Base;")
+    );
+    assert!(!generated.contains("Source.Removed_Gone;"));
+    assert_well_formed(&project, &[&generated]);
+
+    let options = GeneratorOptions {
+        from_string: false,
+        enumeration: false,
+        is_deployable: false,
+        ..Default::default()
+    };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+    assert!(!generated.contains("fromString"));
+    assert!(!generated.contains("function all()"));
+    assert!(!generated.contains("function count()"));
+    assert!(!generated.contains("isDeployable"));
+    assert!(generated.ends_with("        revert UnknownMetaArtifact();\n    }\n}\n"));
+    assert_well_formed(&project, &[&generated]);
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_lookup_helpers_compiles() {
+    let mut lock = SourceLock::default();
+    let names = ["Token", "Gone", "Base", "IToken", "Impl", "MathLib"].map(String::from);
+    lock.update(&names).unwrap();
+    lock.update(&names.into_iter().filter(|n| n != "Gone").collect::<Vec<_>>()).unwrap();
+    assert_fixture_compiles("mixed", GeneratorOptions { lock: Some(lock), ..Default::default() });
+}
+
+#[test]
+fn test_generator_constructor_arguments() {
+    let project = fixture("constructors");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let generated = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap();
+
+    assert!(generated.contains(
+        "function toCreationCode(Source metaArtifact, bytes memory constructorArgs) internal pure returns (bytes memory)"
+    ));
+    // contract-typed parameters are passed as addresses
+    assert!(generated.contains(
+        "function toCreationCode(Source metaArtifact, address arg0, uint256[] memory arg1, address arg2, string memory arg3)"
+    ));
+    assert!(generated.contains(
+        "return abi.encodePacked(type(_Vault).creationCode, abi.encode(arg0, arg1, arg2, arg3));"
+    ));
+    // contracts with the same parameter types share one overload
+    assert!(generated.contains("function toCreationCode(Source metaArtifact, address arg0)"));
+    assert!(
+        generated
+            .contains("return abi.encodePacked(type(_Treasury).creationCode, abi.encode(arg0));")
+    );
+    assert!(
+        generated
+            .contains("return abi.encodePacked(type(_Escrow).creationCode, abi.encode(arg0));")
+    );
+    assert!(!generated.contains("address payable arg0"));
+    // parameters that would need an import only get the raw bytes overload
+    assert!(!generated.contains("type(_Configured).creationCode, abi.encode"));
+    assert!(!generated.contains("type(_NoArgs).creationCode, abi.encode"));
+    // a `bytes` parameter is passed through the raw overload, which has the same signature
+    assert!(!generated.contains("type(_Blob).creationCode, abi.encode"));
+    assert_eq!(
+        generated.matches("function toCreationCode(Source metaArtifact, bytes memory").count(),
+        1
+    );
+
+    assert_well_formed(&project, &[&generated]);
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_constructor_arguments_compiles() {
+    assert_fixture_compiles("constructors", GeneratorOptions::default());
+}
+
+#[test]
+fn test_generator_smoke_tests() {
+    let project = fixture("constructors");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let mut options = GeneratorOptions::default();
+    options.paths.output = "script/reflections/di/libraries/Sources.s.sol".to_string();
+    let generated = generate_smoke_tests(&contracts, &options, "script/reflections/di").unwrap();
+
+    assert!(
+        generated
+            .contains("import { Sources } from \"script/reflections/di/libraries/Sources.s.sol\";")
+    );
+    assert!(generated.contains(
+        "import { StdConfigBasedWiring } from \"script/reflections/di/wiring/StdConfigBasedWiring.s.sol\";"
+    ));
+    assert!(generated.contains("contract SourcesSmokeTest is Test {"));
+    assert!(generated.contains(
+        "    function test_NoArgs() public {\n        assertWired(Sources.NoArgs);\n    }"
+    ));
+    // sources with constructor arguments are deployed with zero values by the test itself
+    assert!(generated.contains(
+        "        address arg0;\n        uint256[] memory arg1;\n        address arg2;\n        string memory arg3;\n        assertDeployed(Sources.Vault, Sources.toCreationCode(Sources.Vault, arg0, arg1, arg2, arg3));"
+    ));
+    assert!(generated.contains(
+        "        bytes memory arg0;\n        assertDeployed(Sources.Blob, Sources.toCreationCode(Sources.Blob, abi.encode(arg0)));"
+    ));
+    assert!(generated.contains(
+        "    function test_Configured() public {\n        // Configured takes constructor arguments whose types need their own imports\n        vm.skip(true);\n    }"
+    ));
+    assert!(!generated.contains("test_IToken"));
+    assert!(!generated.contains("predictAddress"));
+
+    options.init_code_hashes = Some([("NoArgs".to_string(), keccak256(b"NoArgs"))].into());
+    let generated = generate_smoke_tests(&contracts, &options, "script/reflections/di").unwrap();
+    assert!(generated.contains(
+        "assertEq(deployed, Sources.predictAddress(Sources.NoArgs, address(wiring)), \"predictAddress\");"
+    ));
+
+    // sharded sources are constants of the dispatcher
+    options.sharding = Sharding::Count;
+    let generated = generate_smoke_tests(&contracts, &options, "script/reflections/di").unwrap();
+    assert!(generated.contains("address deployed = assertWired(Sources.NoArgs);"));
+    // the dispatcher only has the raw overload
+    assert!(generated.contains(
+        "assertDeployed(Sources.Escrow, Sources.toCreationCode(Sources.Escrow, abi.encode(arg0)));"
+    ));
+    // and everything the tests take from the library is declared by it
+    let dispatcher = &generate_libraries(&contracts, &options).unwrap()[0].code;
+    let tests = &generated[generated.find("contract SourcesSmokeTest").unwrap()..];
+    for (i, _) in tests.match_indices("Sources.") {
+        let ident: String = tests[i + "Sources.".len()..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        assert!(
+            dispatcher.contains(&format!("constant {ident} =")) ||
+                dispatcher.contains(&format!("function {ident}(")) ||
+                dispatcher.contains(&format!("type {ident} is")),
+            "`Sources.{ident}` is not declared"
+        );
+    }
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_smoke_tests_compile() {
+    let options = GeneratorOptions {
+        init_code_hashes: Some([("NoArgs".to_string(), keccak256(b"NoArgs"))].into()),
+        sharding: Sharding::Count,
+        shard_size: 3,
+        ..Default::default()
+    };
+    assert_fixture_compiles("constructors", options);
+}
+
+#[test]
+fn test_generator_duplicate_names() {
+    let project = fixture("duplicates");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let err = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap_err();
+
+    match err {
+        GeneratorError::DuplicateContract { name, paths } => {
+            assert_eq!(name, "Vault");
+            assert_eq!(paths, ["src/Vault.sol", "src/v2/Vault.sol"]);
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+}
+
+#[test]
+fn test_generator_disambiguate_duplicate_names() {
+    let project = fixture("duplicates");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let options = GeneratorOptions { disambiguate: true, ..Default::default() };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+
+    assert!(generated.contains(r#"import { Vault as _Vault } from "src/Vault.sol";"#));
+    assert!(generated.contains(r#"import { Vault as _Vault_v2 } from "src/v2/Vault.sol";"#));
+    assert!(generated.contains(r#"import { Router as _Router } from "src/v2/Vault.sol";"#));
+    assert!(generated.contains("        Vault,\n        Vault_v2"));
+    assert!(generated.contains("return type(_Vault_v2).creationCode;"));
+    assert!(generated.contains(r#"return "Vault_v2";"#));
+
+    assert_well_formed(&project, &[&generated]);
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_disambiguate_duplicate_names_compiles() {
+    assert_fixture_compiles(
+        "duplicates",
+        GeneratorOptions { disambiguate: true, ..Default::default() },
+    );
+}
+
+#[test]
+fn test_generator_resolve_pragma() {
+    let project = fixture("pragmas");
+    let contracts = discover_contracts(&project, "src").unwrap();
+
+    // the ^0.7.6 interface cannot be imported together with the ^0.8.20 contract
+    let options =
+        GeneratorOptions { solidity_version: AUTO_PRAGMA.to_string(), ..Default::default() };
+    match resolve_pragma(&contracts, &ImportGraph::default(), &options).unwrap_err() {
+        GeneratorError::NoCompatiblePragma { paths } => {
+            assert!(paths.contains(&"src/legacy/IOracle.sol (^0.7.6)".to_string()), "{paths:?}");
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+
+    // without interfaces, the tightest pragma is the one of Pool.sol
+    let mut options = GeneratorOptions {
+        solidity_version: AUTO_PRAGMA.to_string(),
+        enum_policy: EnumPolicy::Deployable,
+        ..Default::default()
+    };
+    assert_eq!(resolve_pragma(&contracts, &ImportGraph::default(), &options).unwrap(), "^0.8.20");
+
+    options.solidity_version = "^0.8.0".to_string();
+    assert_eq!(resolve_pragma(&contracts, &ImportGraph::default(), &options).unwrap(), "^0.8.0");
+
+    options.solidity_version = ">=0.8.0 <0.8.20".to_string();
+    match resolve_pragma(&contracts, &ImportGraph::default(), &options).unwrap_err() {
+        GeneratorError::IncompatiblePragma { requested, required } => {
+            assert_eq!(requested, ">=0.8.0 <0.8.20");
+            assert_eq!(required, "^0.8.20");
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+
+    options.solidity_version = "^0.8.20".to_string();
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+    assert!(generated.contains("pragma solidity ^0.8.20;"));
+    // the ^0.7.6 interface is left out, so nothing imports it
+    assert!(!generated.contains("IOracle"));
+    assert_well_formed(&project, &[&generated]);
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_resolve_pragma_compiles() {
+    let options = GeneratorOptions {
+        solidity_version: "^0.8.20".to_string(),
+        enum_policy: EnumPolicy::Deployable,
+        ..Default::default()
+    };
+    assert_fixture_compiles("pragmas", options);
+}
+
+#[test]
+fn test_generator_resolve_pragma_of_imports() {
+    let dir = testdir!();
+    fs::create_dir_all(dir.join("src/utils")).unwrap();
+    fs::write(
+        dir.join("src/Pool.sol"),
+        "pragma solidity ^0.8.0;\n\nimport { Position } from \"./utils/Types.sol\";\n\ncontract Pool {}\n",
+    )
+    .unwrap();
+    let paths = Paths::with_root_and_config(&dir, None).unwrap();
+    let resolver = ImportResolver::new(&paths).unwrap();
+    let options = GeneratorOptions {
+        solidity_version: AUTO_PRAGMA.to_string(),
+        enum_policy: EnumPolicy::Deployable,
+        ..Default::default()
+    };
+
+    // Types.sol declares no contract, but Pool.sol compiles it in
+    fs::write(
+        dir.join("src/utils/Types.sol"),
+        "pragma solidity >=0.8.4 <0.8.20;\n\nstruct Position {\n    uint256 amount;\n}\n",
+    )
+    .unwrap();
+    let contracts = discover_contracts(&dir, "src").unwrap();
+    let imports = ImportGraph::from_files(["src/Pool.sol".to_string()], &resolver);
+    assert_eq!(resolve_pragma(&contracts, &imports, &options).unwrap(), ">=0.8.12 <0.8.20");
+
+    fs::write(
+        dir.join("src/utils/Types.sol"),
+        "pragma solidity >=0.8.4 <0.8.10;\n\nstruct Position {\n    uint256 amount;\n}\n",
+    )
+    .unwrap();
+    let imports = ImportGraph::from_files(["src/Pool.sol".to_string()], &resolver);
+    let err = resolve_pragma(&contracts, &imports, &options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "no compiler version satisfies the generated library (^0.8.12) and all imported files: \
+         src/Pool.sol (^0.8.0), src/utils/Types.sol (>=0.8.4 <0.8.10)"
+    );
+}
+
+#[test]
+fn test_discovery_cache() {
+    let dir = testdir!();
+    copy_dir_recursive(fixture("mixed"), &dir).unwrap();
+    let filter = DiscoveryFilter::default();
+    let cold = discover_contracts(&dir, "src").unwrap();
+
+    let mut cache = DiscoveryCache::load(&dir);
+    assert_eq!(discover_contracts_cached(&dir, "src", &filter, &mut cache).unwrap(), cold);
+    cache.save().unwrap();
+    assert!(dir.join(DISCOVERY_CACHE_PATH).exists());
+
+    let mut cache = DiscoveryCache::load(&dir);
+    assert_eq!(discover_contracts_cached(&dir, "src", &filter, &mut cache).unwrap(), cold);
+
+    // unchanged files are not parsed again
+    let cache_file = fs::read_to_string(dir.join(DISCOVERY_CACHE_PATH)).unwrap();
+    fs::write(
+        dir.join(DISCOVERY_CACHE_PATH),
+        cache_file.replace(r#""name":"MathLib""#, r#""name":"CachedLib""#),
+    )
+    .unwrap();
+    let mut cache = DiscoveryCache::load(&dir);
+    let warm = discover_contracts_cached(&dir, "src", &filter, &mut cache).unwrap();
+    assert!(warm.iter().any(|c| c.name == "CachedLib"));
+
+    // changed files are
+    let token = dir.join("src/tokens/Token.sol");
+    fs::write(
+        &token,
+        fs::read_to_string(&token).unwrap().replace("contract Token ", "contract Renamed "),
+    )
+    .unwrap();
+    let warm = discover_contracts_cached(&dir, "src", &filter, &mut cache).unwrap();
+    assert!(warm.iter().any(|c| c.name == "Renamed"));
+    assert!(!warm.iter().any(|c| c.name == "Token"));
+}
+
+#[test]
+fn test_project_model() {
+    let project = fixture("model");
+    let model = ProjectModel::discover(&project, "src", &DiscoveryFilter::default()).unwrap();
+
+    let declarations: Vec<_> = model
+        .declarations()
+        .map(|d| (d.qualified_name(), d.declaration.kind.as_str(), d.location()))
+        .collect();
+    assert_eq!(
+        declarations,
+        [
+            ("Shares".to_string(), "type", "src/Vault.sol:4".to_string()),
+            ("Position".to_string(), "struct", "src/Vault.sol:6".to_string()),
+            ("Unauthorized".to_string(), "error", "src/Vault.sol:11".to_string()),
+            ("Paused".to_string(), "event", "src/Vault.sol:13".to_string()),
+            ("toShares".to_string(), "function", "src/Vault.sol:15".to_string()),
+            ("Vault.Status".to_string(), "enum", "src/Vault.sol:20".to_string()),
+            ("Vault.Deposit".to_string(), "struct", "src/Vault.sol:25".to_string()),
+            ("Vault.Deposited".to_string(), "event", "src/Vault.sol:30".to_string()),
+            ("Vault.InsufficientBalance".to_string(), "error", "src/Vault.sol:32".to_string()),
+            ("Vault.deposit".to_string(), "function", "src/Vault.sol:42".to_string()),
+            ("Vault.balanceOf".to_string(), "function", "src/Vault.sol:47".to_string()),
+        ]
+    );
+
+    let find = |name: &str| {
+        model.declarations().find(|d| d.qualified_name() == name).expect("declaration should exist")
+    };
+    match &find("Vault.deposit").declaration.kind {
+        DeclarationKind::Function { parameters, returns, visibility, state_mutability } => {
+            assert_eq!(parameters[0].type_name, "uint256");
+            assert_eq!(returns[0].type_name, "Shares");
+            assert_eq!(visibility.as_deref(), Some("external"));
+            assert_eq!(state_mutability.as_deref(), Some("payable"));
+        }
+        kind => panic!("unexpected kind: {kind:?}"),
+    }
+    match &find("Vault.Deposited").declaration.kind {
+        DeclarationKind::Event { parameters, anonymous } => {
+            assert!(parameters[0].indexed);
+            assert_eq!(parameters[0].name.as_deref(), Some("owner"));
+            assert!(!parameters[1].indexed);
+            assert!(!anonymous);
+        }
+        kind => panic!("unexpected kind: {kind:?}"),
+    }
+    assert!(matches!(
+        &find("Paused").declaration.kind,
+        DeclarationKind::Event { anonymous: true, .. }
+    ));
+    match &find("Vault.Status").declaration.kind {
+        DeclarationKind::Enum { variants } => assert_eq!(variants, &["Active", "Paused"]),
+        kind => panic!("unexpected kind: {kind:?}"),
+    }
+    match &find("Position").declaration.kind {
+        DeclarationKind::Struct { fields } => {
+            assert_eq!(fields[1].type_name, "mapping(address => uint256)");
+            assert_eq!(fields[1].name.as_deref(), Some("allowances"));
+        }
+        kind => panic!("unexpected kind: {kind:?}"),
+    }
+    assert_eq!(
+        find("Shares").declaration.kind,
+        DeclarationKind::UserDefinedValueType { underlying: "uint256".to_string() }
+    );
+
+    let contracts = model.contracts();
+    assert_eq!(contracts.len(), 1);
+    assert_eq!(contracts[0].constructor_params[0].type_name, "address");
+}
+
+#[test]
+fn test_import_graph() {
+    let project = fixture("imports");
+    let paths = Paths::with_root_and_config(&project, None).unwrap();
+    let resolver = ImportResolver::new(&paths).unwrap();
+    let model = ProjectModel::discover(&project, "src", &DiscoveryFilter::default()).unwrap();
+    let graph = ImportGraph::build(&model, &resolver);
+
+    assert_eq!(
+        graph.dependencies("src/Vault.sol").collect::<Vec<_>>(),
+        ["lib/forge-std/src/Test.sol", "lib/math/src/FixedPoint.sol", "src/access/Owned.sol"]
+    );
+    // dependencies are followed transitively
+    assert_eq!(
+        graph.dependencies("lib/forge-std/src/Test.sol").collect::<Vec<_>>(),
+        ["lib/forge-std/src/Vm.sol"]
+    );
+    assert_eq!(graph.dependents("src/access/Owned.sol").collect::<Vec<_>>(), ["src/Vault.sol"]);
+    assert_eq!(
+        graph.diagnostics,
+        [
+            ImportError::Unresolved {
+                file: "src/Vault.sol".to_string(),
+                import: "@missing/Oracle.sol".to_string(),
+                line: 8,
+            },
+            ImportError::Unresolved {
+                file: "src/access/Owned.sol".to_string(),
+                import: "../utils/Errors.sol".to_string(),
+                line: 4,
+            },
+        ]
+    );
+    assert_eq!(ImportGraph::from_dir("src", &resolver), graph);
+}
+
+#[test]
+fn test_artifact_discovery() {
+    let project = fixture("artifacts");
+    let paths = Paths::with_root_and_config(&project, None).unwrap();
+    let resolver = ImportResolver::new(&paths).unwrap();
+    let index = ArtifactIndex::load(&project, "out").unwrap();
+    // compiled twice, listed once; build-info files are skipped
+    assert_eq!(index.contracts.len(), 5);
+
+    let dependencies = ["lib/math/src/Math.sol:Math".parse::<Dependency>().unwrap()];
+    let contracts =
+        index.contracts("src", &DiscoveryFilter::default(), &dependencies, &resolver).unwrap();
+    let summary: Vec<_> =
+        contracts.iter().map(|c| (c.name.as_str(), c.import_path.as_str(), c.kind)).collect();
+    assert_eq!(
+        summary,
+        [
+            ("Base", "src/Vault.sol", ContractKind::AbstractContract),
+            // no AST, so only the missing bytecode is known
+            ("IVault", "src/IVault.sol", ContractKind::AbstractContract),
+            ("Math", "@math/Math.sol", ContractKind::Library),
+            ("Vault", "src/Vault.sol", ContractKind::Contract),
+        ]
+    );
+
+    let vault = &contracts[3];
+    assert_eq!(vault.pragma.as_ref().unwrap().to_string(), "^0.8.20");
+    assert_eq!(vault.natspec.tag("@custom:swagger"), Some("Core"));
+    assert_eq!(vault.root_base(&contracts).map(|c| c.name.as_str()), Some("Base"));
+    let params: Vec<_> = vault
+        .constructor_params
+        .iter()
+        .map(|p| (p.type_name.as_str(), p.name.as_deref()))
+        .collect();
+    assert_eq!(
+        params,
+        [("address", Some("owner")), ("Vault.Config", Some("config")), ("IVault", None)]
+    );
+
+    let missing = ["lib/math/src/Math.sol:Other".parse::<Dependency>().unwrap()];
+    assert!(matches!(
+        index.contracts("src", &DiscoveryFilter::default(), &missing, &resolver),
+        Err(ReflectionsError::GeneratorError(GeneratorError::MissingArtifact(_)))
+    ));
+    assert!(ArtifactIndex::load(&project, "missing").is_err());
+}
+
+#[test]
+fn test_artifacts_compiled_twice() {
+    // the same creation code from both compiler versions
+    let project = fixture("artifacts");
+    let index = ArtifactIndex::load(&project, "out").unwrap();
+    let math = index.contracts.iter().find(|c| c.name == "Math").unwrap();
+    assert!(index.init_code_hash(math).unwrap().is_some());
+
+    let dir = testdir!();
+    copy_dir_recursive(&project, &dir).unwrap();
+    let artifact = dir.join("out/Math.sol/Math.0.8.24.json");
+    let content = fs::read_to_string(&artifact).unwrap();
+    fs::write(&artifact, content.replace("0x6055", "0x6056")).unwrap();
+    let index = ArtifactIndex::load(&dir, "out").unwrap();
+    let math = index.contracts.iter().find(|c| c.name == "Math").unwrap();
+    let err = index.init_code_hash(math).unwrap_err();
+    assert!(matches!(err, GeneratorError::AmbiguousArtifact(ref name) if name == "Math"), "{err}");
+}
+
+#[test]
+fn test_diff_library() {
+    assert_eq!(diff_library("Sources.s.sol", "a\nb\n", "a\nb\n"), None);
+
+    let diff = diff_library("Sources.s.sol", "a\nb\nc\n", "a\nB\nc\n").unwrap();
+    assert!(diff.starts_with("--- a/Sources.s.sol\n+++ b/Sources.s.sol\n"), "{diff}");
+    assert!(diff.contains("@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"), "{diff}");
+}
+
+#[test]
+fn test_generator_init_code_hashes() {
+    let project = fixture("artifacts");
+    let index = ArtifactIndex::load(&project, "out").unwrap();
+    let resolver =
+        ImportResolver::new(&Paths::with_root_and_config(&project, None).unwrap()).unwrap();
+    let contracts = index.contracts("src", &DiscoveryFilter::default(), &[], &resolver).unwrap();
+    let vault = contracts.iter().find(|c| c.name == "Vault").unwrap();
+    let hash = index.init_code_hash(vault).unwrap().unwrap();
+    assert_eq!(hash, keccak256(&decode_hex("6080604052348015600e575f80fd5b50").unwrap()));
+
+    let mut options = GeneratorOptions::default();
+    let hashes = source_variants(&contracts, &options)
+        .unwrap()
+        .into_iter()
+        .filter_map(|(ident, contract)| Some((ident, index.init_code_hash(contract).unwrap()?)))
+        .collect();
+    options.init_code_hashes = Some(hashes);
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+    // Vault takes constructor arguments, so its hash is left out
+    assert!(generated.contains(
+        "        if (metaArtifact == Source.Vault) {\n            revert UnknownMetaArtifact(); // takes constructor arguments"
+    ));
+    assert!(generated.contains(
+        "        if (metaArtifact == Source.Base) {\n            revert NonDeployableMetaArtifact(); // abstract contract"
+    ));
+    assert!(generated.contains(
+        "function predictAddress(Source metaArtifact, address deployer, ShortString nickname) internal pure returns (address)"
+    ));
+
+    options.sharding = Sharding::Count;
+    options.shard_size = 1;
+    let libraries = generate_libraries(&contracts, &options).unwrap();
+    assert!(libraries[0].code.contains("return Sources1.initCodeHash(Sources1.Source(ordinal));"));
+    assert!(
+        libraries[0]
+            .code
+            .contains("function predictAddress(Source metaArtifact, address deployer)")
+    );
+
+    let generated = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap();
+    assert!(!generated.contains("initCodeHash"));
+    assert!(!generated.contains("predictAddress"));
+}
+
+#[test]
+fn test_generator_init_code_hashes_constructor_args() {
+    let project = fixture("constructors");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let hashes = ["Vault", "NoArgs"].map(|name| (name.to_string(), keccak256(name.as_bytes())));
+    let mut options =
+        GeneratorOptions { init_code_hashes: Some(hashes.into()), ..Default::default() };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+
+    // the constructor arguments are part of the init code, so a hash of the creation code alone
+    // would predict an address the source is never deployed to
+    assert!(generated.contains(
+        "        if (metaArtifact == Source.Vault) {\n            revert UnknownMetaArtifact(); // takes constructor arguments"
+    ));
+    assert!(generated.contains(&format!(
+        "        if (metaArtifact == Source.NoArgs) {{\n            return 0x{};",
+        encode_hex(&keccak256(b"NoArgs"))
+    )));
+
+    // a rebuild changing the creation code makes the hash stale
+    let mut rebuilt = options.init_code_hashes.clone().unwrap();
+    rebuilt.insert("NoArgs".to_string(), keccak256(b"rebuilt"));
+    options.init_code_hashes = Some(rebuilt);
+    let regenerated = generate_sources_library(&contracts, &options).unwrap();
+    assert_eq!(stale_init_code_hashes(&generated, &regenerated), ["NoArgs"]);
+    assert!(stale_init_code_hashes(&generated, &generated).is_empty());
+}
+
+#[test]
+fn test_generator_salt_namespace() {
+    let project = fixture("mixed");
+    let contracts = discover_contracts(&project, "src").unwrap();
+    let generated = generate_sources_library(&contracts, &GeneratorOptions::default()).unwrap();
+    assert!(generated.contains(
+        "function toSalt(Source metaArtifact) internal pure returns (bytes32) {\n        return keccak256(abi.encodePacked(toString(metaArtifact)));"
+    ));
+    assert!(!generated.contains("SALT_NAMESPACE"));
+
+    let namespace = SaltNamespace { project: "acme".into(), version: "v2".into(), chain_id: false };
+    let options = GeneratorOptions { salt_namespace: namespace.clone(), ..Default::default() };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+    assert!(generated.contains(
+        "bytes32 public constant SALT_NAMESPACE = keccak256(abi.encode(string(\"acme\"), string(\"v2\")));"
+    ));
+    assert!(generated.contains(
+        "return keccak256(abi.encodePacked(SALT_NAMESPACE, toString(metaArtifact), nickname.toString()));"
+    ));
+
+    // the chain id makes the salts, and the addresses derived from them, read the chain
+    let options = GeneratorOptions {
+        salt_namespace: SaltNamespace { chain_id: true, ..namespace },
+        init_code_hashes: Some(Default::default()),
+        ..Default::default()
+    };
+    let generated = generate_sources_library(&contracts, &options).unwrap();
+    assert!(generated.contains(
+        "function toSalt(Source metaArtifact) internal view returns (bytes32) {\n        return keccak256(abi.encodePacked(SALT_NAMESPACE, block.chainid, toString(metaArtifact)));"
+    ));
+    assert!(generated.contains(
+        "function predictAddress(Source metaArtifact, address deployer) internal view returns (address)"
+    ));
+    // nothing reading the chain, directly or through the salts, may stay `pure`
+    for function in generated.split("    function ").skip(1) {
+        let (header, body) = function.split_once('\n').unwrap();
+        if body.contains("block.chainid") || body.contains("toSalt(") {
+            assert!(!header.contains(" pure "), "{header}");
+        }
+    }
+    assert_well_formed(&project, &[&generated]);
+
+    let options = GeneratorOptions {
+        salt_namespace: SaltNamespace { project: "ac\"me".into(), ..Default::default() },
+        ..Default::default()
+    };
+    assert!(matches!(
+        generate_sources_library(&contracts, &options),
+        Err(GeneratorError::InvalidSaltNamespace(_))
+    ));
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_salt_namespace_compiles() {
+    let options = GeneratorOptions {
+        salt_namespace: SaltNamespace {
+            project: "acme".into(),
+            version: "v2".into(),
+            chain_id: true,
+        },
+        init_code_hashes: Some(Default::default()),
+        ..Default::default()
+    };
+    assert_fixture_compiles("mixed", options);
+}
+
+#[test]
+fn test_generator_sharding() {
+    let project = fixture("mixed");
+    let contracts = discover_contracts(&project, "src").unwrap();
+
+    let options =
+        GeneratorOptions { sharding: Sharding::Count, shard_size: 2, ..Default::default() };
+    let libraries = generate_libraries(&contracts, &options).unwrap();
+    let names: Vec<_> = libraries.iter().map(|l| l.library_name.as_str()).collect();
+    assert_eq!(names, ["Sources", "Sources1", "Sources2", "Sources3"]);
+    let dispatcher = &libraries[0].code;
+    assert!(dispatcher.contains("type Source is uint256;"));
+    assert!(dispatcher.contains("import { Sources1 } from \"./Sources1.s.sol\";"));
+    assert!(dispatcher.contains("Source internal constant Base = Source.wrap((1 << 8) | 2);"));
+    assert!(dispatcher.contains("Source internal constant Token = Source.wrap((3 << 8) | 2);"));
+    assert!(
+        dispatcher
+            .contains("function fromShard(Sources2.Source source) internal pure returns (Source)")
+    );
+    assert!(dispatcher.contains("return Sources3.toCreationCode(Sources3.Source(ordinal));"));
+    assert!(libraries[3].code.contains("library Sources3 {"));
+    assert!(libraries[3].code.contains("        TransparentUpgradeableProxy,\n        Token\n"));
+    assert_well_formed(&project, &libraries.iter().map(|l| l.code.as_str()).collect::<Vec<_>>());
+
+    // shard ids of directories come from the lockfile when they are locked
+    let mut options = GeneratorOptions { sharding: Sharding::Directory, ..Default::default() };
+    options.paths.contracts_dir = "src".to_string();
+    let mut lock = SourceLock::default();
+    lock.update(&variant_names(&contracts, &options).unwrap()).unwrap();
+    let shards = variant_shards(&contracts, &options).unwrap();
+    assert_eq!(shards["Token"], "tokens");
+    assert_eq!(shards["Base"], "src");
+    lock.shards = vec!["tokens".to_string()];
+    lock.assign_shards(&shards);
+    options.lock = Some(lock);
+
+    let libraries = generate_libraries(&contracts, &options).unwrap();
+    let names: Vec<_> = libraries.iter().map(|l| l.library_name.as_str()).collect();
+    assert_eq!(names, ["Sources", "SourcesTokens", "SourcesSrc"]);
+    assert!(
+        libraries[0].code.contains("Source internal constant IToken = Source.wrap((1 << 8) | 2);")
+    );
+    assert!(
+        libraries[0].code.contains("Source internal constant MathLib = Source.wrap((2 << 8) | 4);")
+    );
+    assert_well_formed(&project, &libraries.iter().map(|l| l.code.as_str()).collect::<Vec<_>>());
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_sharding_by_count_compiles() {
+    assert_fixture_compiles(
+        "mixed",
+        GeneratorOptions { sharding: Sharding::Count, shard_size: 2, ..Default::default() },
+    );
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_generator_sharding_by_directory_compiles() {
+    let mut options = GeneratorOptions { sharding: Sharding::Directory, ..Default::default() };
+    options.paths.contracts_dir = "src".to_string();
+    assert_fixture_compiles("mixed", options);
+}
+
+#[test]
+fn test_generator_too_many_variants() {
+    let project = fixture("mixed");
+    let contracts = discover_contracts(&project, "src").unwrap();
+
+    let mut lock = SourceLock::default();
+    lock.update(&(0..255).map(|i| format!("Contract{i}")).collect::<Vec<_>>()).unwrap();
+    let options = GeneratorOptions { lock: Some(lock), ..Default::default() };
+    let err = generate_sources_library(&contracts, &options).unwrap_err();
+    // the 5 contracts of the fixture are appended to the locked ones
+    assert!(matches!(err, GeneratorError::TooManyVariants { count: 260, .. }), "{err}");
+
+    let options =
+        GeneratorOptions { sharding: Sharding::Count, shard_size: 0, ..Default::default() };
+    let err = generate_libraries(&contracts, &options).unwrap_err();
+    assert!(matches!(err, GeneratorError::InvalidShardSize(0)), "{err}");
+}